@@ -0,0 +1,423 @@
+use crate::{
+    command::Command,
+    network::packet::{
+        OpenRGBPackets, RequestControllerCountPacket, RequestControllerDataPacket,
+        RequestProfileListPacket,
+    },
+    Notifier, OpenRGBClient, OpenRGBDevice, OpenRGBError, OpenRGBResult, OpenRGBServer, RawPacket,
+    ServerHandler, Transport,
+};
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    net::{lookup_host, TcpListener, ToSocketAddrs},
+    sync::Mutex,
+};
+
+struct Upstream {
+    prefix: String,
+    client: Mutex<OpenRGBClient>,
+}
+
+/// How many devices every upstream has, along with the aggregated numbering built from them.
+#[derive(Debug, Default)]
+struct DeviceList {
+    counts: Vec<usize>,
+    /// The upstream and device id of every aggregated device.
+    devices: Vec<(usize, usize)>,
+}
+
+impl DeviceList {
+    fn set_count(&mut self, upstream_id: usize, count: usize) {
+        if self.counts.len() <= upstream_id {
+            self.counts.resize(upstream_id + 1, 0);
+        }
+        self.counts[upstream_id] = count;
+
+        self.devices = self
+            .counts
+            .iter()
+            .enumerate()
+            .flat_map(|(upstream_id, count)| (0..*count).map(move |id| (upstream_id, id)))
+            .collect();
+    }
+}
+
+/// Combines the devices of several OpenRGB servers into one list, served as if it came from a
+/// single server.
+///
+/// Devices are numbered in the order their servers were added and named after the prefix of
+/// their server, such as `desk: Corsair K70`. Updates are forwarded to the server owning the
+/// device, profiles are saved on every server and loaded or deleted on the servers that have
+/// them.
+///
+/// The device list is kept until a server reports that its devices changed, then only that
+/// server is asked again and clients are sent `DeviceListUpdated` in turn. Requests for devices
+/// of different servers don't wait on each other.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let mut aggregator = Aggregator::new();
+/// aggregator.connect("desk", "192.168.1.10:6742").await?;
+/// aggregator.connect("shelf", "192.168.1.11:6742").await?;
+///
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:6742").await?;
+/// aggregator.serve(listener).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Aggregator {
+    upstreams: Vec<Arc<Upstream>>,
+    /// Connections to the servers only listened to for `DeviceListUpdated`, by upstream id.
+    watchers: Vec<(usize, Box<dyn Transport>)>,
+    devices: std::sync::Mutex<DeviceList>,
+    /// Notifiers of the connected clients, by connection id.
+    clients: std::sync::Mutex<HashMap<usize, Notifier>>,
+    next_client_id: AtomicUsize,
+}
+
+impl Aggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects to an OpenRGB server, adding its devices under the prefix.
+    pub async fn connect<S: Into<String>, A: ToSocketAddrs>(
+        &mut self,
+        prefix: S,
+        address: A,
+    ) -> OpenRGBResult<()> {
+        let addresses: Vec<SocketAddr> = lookup_host(address).await?.collect();
+        let client = OpenRGBClient::connect(addresses.as_slice(), "openrgb-aggregator").await?;
+        let watcher = OpenRGBClient::connect(addresses.as_slice(), "openrgb-aggregator").await?;
+
+        self.watchers
+            .push((self.upstreams.len(), watcher.into_transport()));
+        self.add_client(prefix, client);
+        self.refresh_upstream(self.upstreams.len() - 1).await
+    }
+
+    /// Adds the devices of an already connected client under the prefix, available after the
+    /// next [`Aggregator::refresh`].
+    ///
+    /// Unlike servers added with [`Aggregator::connect`], changes to its devices are only picked
+    /// up by calling [`Aggregator::refresh`].
+    pub fn add_client<S: Into<String>>(&mut self, prefix: S, client: OpenRGBClient) {
+        self.upstreams.push(Arc::new(Upstream {
+            prefix: prefix.into(),
+            client: Mutex::new(client),
+        }));
+    }
+
+    /// Requests the number of devices of every server and numbers them again, returning the
+    /// total number of devices.
+    pub async fn refresh(&self) -> OpenRGBResult<usize> {
+        for upstream_id in 0..self.upstreams.len() {
+            self.refresh_upstream(upstream_id).await?;
+        }
+
+        Ok(self.device_count())
+    }
+
+    async fn refresh_upstream(&self, upstream_id: usize) -> OpenRGBResult<()> {
+        let count = self.upstreams[upstream_id]
+            .client
+            .lock()
+            .await
+            .get_device_count()
+            .await?;
+
+        self.device_list().set_count(upstream_id, count);
+        Ok(())
+    }
+
+    fn device_list(&self) -> std::sync::MutexGuard<'_, DeviceList> {
+        self.devices.lock().expect("device list lock poisoned")
+    }
+
+    pub fn device_count(&self) -> usize {
+        self.device_list().devices.len()
+    }
+
+    /// Finds the prefix of the server owning an aggregated device and the device's id on it.
+    pub fn locate(&self, device_id: usize) -> Option<(&str, usize)> {
+        let (upstream_id, id) = *self.device_list().devices.get(device_id)?;
+        Some((self.upstreams[upstream_id].prefix.as_str(), id))
+    }
+
+    /// Requests an aggregated device from its server, named after the server's prefix.
+    pub async fn get_device(&self, device_id: usize) -> OpenRGBResult<OpenRGBDevice> {
+        let (upstream, id) = self.upstream(device_id)?;
+        let mut device = upstream.client.lock().await.get_device(id).await?;
+        device.name = format!("{}: {}", upstream.prefix, device.name);
+        Ok(device)
+    }
+
+    fn upstream(&self, device_id: usize) -> OpenRGBResult<(&Upstream, usize)> {
+        let (upstream_id, id) = *self
+            .device_list()
+            .devices
+            .get(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+        Ok((&self.upstreams[upstream_id], id))
+    }
+
+    /// Answers a request made to the aggregated server.
+    pub async fn handle(&self, request: RawPacket) -> OpenRGBResult<Vec<RawPacket>> {
+        let device_id = request.device as usize;
+        let command = match Command::try_from(request.command) {
+            Ok(command) => command,
+            // Like OpenRGB, ignore anything we don't understand.
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let response = match command {
+            Command::RequestControllerCount => {
+                let count = self.device_count() as u32;
+                RawPacket::from_packet(0, &RequestControllerCountPacket::new(count)).await?
+            }
+            Command::RequestControllerData => match self.get_device(device_id).await {
+                Ok(device) => {
+                    let packet = RequestControllerDataPacket::new(device);
+                    RawPacket::from_packet(request.device, &packet).await?
+                }
+                Err(OpenRGBError::InvalidId(_)) => return Ok(Vec::new()),
+                Err(error) => return Err(error),
+            },
+            Command::RequestProfileList => {
+                let mut profiles = Vec::new();
+                for upstream in &self.upstreams {
+                    for profile in upstream.client.lock().await.get_profiles().await? {
+                        if !profiles.contains(&profile) {
+                            profiles.push(profile);
+                        }
+                    }
+                }
+
+                RawPacket::from_packet(0, &RequestProfileListPacket::new(profiles)).await?
+            }
+            _ => {
+                self.forward(device_id, request).await?;
+                return Ok(Vec::new());
+            }
+        };
+
+        Ok(vec![response])
+    }
+
+    async fn forward(&self, device_id: usize, request: RawPacket) -> OpenRGBResult<()> {
+        let result = match request.decode().await? {
+            OpenRGBPackets::SaveProfile(packet) => {
+                for upstream in &self.upstreams {
+                    let mut client = upstream.client.lock().await;
+                    client.save_profile(packet.name.clone()).await?;
+                }
+                Ok(())
+            }
+            OpenRGBPackets::LoadProfile(packet) => {
+                for upstream in &self.upstreams {
+                    let mut client = upstream.client.lock().await;
+                    if client.get_profiles().await?.contains(&packet.name) {
+                        client.load_profile(packet.name.clone()).await?;
+                    }
+                }
+                Ok(())
+            }
+            OpenRGBPackets::DeleteProfile(packet) => {
+                for upstream in &self.upstreams {
+                    let mut client = upstream.client.lock().await;
+                    if client.get_profiles().await?.contains(&packet.name) {
+                        client.delete_profile(packet.name.clone()).await?;
+                    }
+                }
+                Ok(())
+            }
+            packet => self.forward_device(device_id, packet).await,
+        };
+
+        // Invalid ids and colors are ignored like OpenRGB does, only losing a server is fatal.
+        match result {
+            Err(OpenRGBError::IO(error)) => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn forward_device(&self, device_id: usize, packet: OpenRGBPackets) -> OpenRGBResult<()> {
+        match packet {
+            OpenRGBPackets::UpdateLeds(_)
+            | OpenRGBPackets::UpdateZoneLeds(_)
+            | OpenRGBPackets::UpdateSingleLed(_)
+            | OpenRGBPackets::UpdateMode(_)
+            | OpenRGBPackets::ResizeZone(_)
+            | OpenRGBPackets::Command(Command::SetCustomMode) => {}
+            // Nothing else changes a device, such as `SetClientName`.
+            _ => return Ok(()),
+        }
+
+        let (upstream, id) = self.upstream(device_id)?;
+        let mut client = upstream.client.lock().await;
+
+        // Loading a profile drops the client's cached devices, which updates are checked
+        // against.
+        if client.device(id).is_none() {
+            client.refresh_device(id).await?;
+        }
+
+        match packet {
+            OpenRGBPackets::UpdateLeds(packet) => client.update_leds(id, &packet.colors).await,
+            OpenRGBPackets::UpdateZoneLeds(packet) => {
+                client
+                    .update_zone_leds(id, packet.zone_id, &packet.colors)
+                    .await
+            }
+            OpenRGBPackets::UpdateSingleLed(packet) => {
+                client
+                    .update_single_led(id, packet.led_id, packet.color)
+                    .await
+            }
+            OpenRGBPackets::UpdateMode(packet) => client.update_mode(id, &packet.mode).await,
+            OpenRGBPackets::ResizeZone(packet) => {
+                client
+                    .resize_zone(id, packet.zone_id, packet.new_size)
+                    .await?;
+                client.refresh_device(id).await.map(|_| ())
+            }
+            OpenRGBPackets::Command(Command::SetCustomMode) => client.set_custom_mode(id).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Serves the aggregated devices to any number of clients.
+    pub async fn serve(mut self, listener: TcpListener) -> OpenRGBResult<()> {
+        let watchers = std::mem::take(&mut self.watchers);
+        let aggregator = Arc::new(self);
+
+        for (upstream_id, watcher) in watchers {
+            tokio::spawn(aggregator.clone().watch(upstream_id, watcher));
+        }
+
+        aggregator.serve_clients(listener).await
+    }
+
+    async fn serve_clients(self: Arc<Self>, listener: TcpListener) -> OpenRGBResult<()> {
+        OpenRGBServer::new(move |_, notifier| {
+            let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+            self.clients
+                .lock()
+                .expect("clients lock poisoned")
+                .insert(client_id, notifier);
+
+            let aggregator = self.clone();
+            async move {
+                Ok(AggregatorHandler {
+                    aggregator,
+                    client_id,
+                })
+            }
+        })
+        .serve(listener)
+        .await
+    }
+
+    /// Numbers the devices again whenever the server reports that its devices changed, passing
+    /// the notification on to every client. Stops once the server disconnects.
+    async fn watch(self: Arc<Self>, upstream_id: usize, mut watcher: Box<dyn Transport>) {
+        while let Ok(Some(packet)) = RawPacket::read(&mut watcher).await {
+            if packet.command != Command::DeviceListUpdated as u32 {
+                continue;
+            }
+
+            if self.refresh_upstream(upstream_id).await.is_err() {
+                break;
+            }
+
+            let notification = RawPacket::new(0, Command::DeviceListUpdated as u32, Vec::new());
+            self.clients
+                .lock()
+                .expect("clients lock poisoned")
+                .retain(|_, client| client.send(notification.clone()));
+        }
+    }
+}
+
+struct AggregatorHandler {
+    aggregator: Arc<Aggregator>,
+    client_id: usize,
+}
+
+impl Drop for AggregatorHandler {
+    fn drop(&mut self) {
+        self.aggregator
+            .clients
+            .lock()
+            .expect("clients lock poisoned")
+            .remove(&self.client_id);
+    }
+}
+
+#[async_trait]
+impl ServerHandler for AggregatorHandler {
+    async fn handle(&mut self, request: RawPacket) -> OpenRGBResult<Vec<RawPacket>> {
+        self.aggregator.handle(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::connection::HEADER_LENGTH;
+    use std::time::Duration;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    fn client_count(aggregator: &Aggregator) -> usize {
+        aggregator
+            .clients
+            .lock()
+            .expect("clients lock poisoned")
+            .len()
+    }
+
+    #[tokio::test]
+    async fn forgets_disconnected_clients() {
+        let aggregator = Arc::new(Aggregator::new());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(aggregator.clone().serve_clients(listener));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let mut request = Vec::new();
+        RawPacket::new(0, Command::RequestControllerCount as u32, Vec::new())
+            .write(&mut request)
+            .await
+            .unwrap();
+        stream.write_all(&request).await.unwrap();
+
+        let mut response = [0; HEADER_LENGTH + 4];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response[HEADER_LENGTH..], &0u32.to_le_bytes());
+        assert_eq!(client_count(&aggregator), 1);
+
+        drop(stream);
+        for _ in 0..100 {
+            if client_count(&aggregator) == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("the notifier of a disconnected client was kept");
+    }
+}
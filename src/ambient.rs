@@ -0,0 +1,428 @@
+use crate::{Color, OpenRGBClient, OpenRGBError, OpenRGBResult};
+
+/// A captured image as tightly packed RGB bytes, row by row from the top left pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RgbFrame<'a> {
+    width: u32,
+    height: u32,
+    data: &'a [u8],
+}
+
+impl<'a> RgbFrame<'a> {
+    /// Wraps a buffer of `width * height * 3` bytes.
+    pub fn new(width: u32, height: u32, data: &'a [u8]) -> OpenRGBResult<Self> {
+        let expected = width as usize * height as usize * 3;
+
+        if data.len() != expected {
+            return Err(OpenRGBError::InvalidFrameSize(expected, data.len()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let index = (y as usize * self.width as usize + x as usize) * 3;
+        Some(Color::new(
+            self.data[index],
+            self.data[index + 1],
+            self.data[index + 2],
+        ))
+    }
+
+    /// Averages the pixels in `x..x_end` and `y..y_end`, clamped to the frame.
+    fn average(&self, x: u32, y: u32, x_end: u32, y_end: u32) -> Color {
+        let (x_end, y_end) = (x_end.min(self.width), y_end.min(self.height));
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+
+        for y in y..y_end {
+            for x in x..x_end {
+                let index = (y as usize * self.width as usize + x as usize) * 3;
+                for (sum, byte) in sum.iter_mut().zip(&self.data[index..index + 3]) {
+                    *sum += *byte as u64;
+                }
+                count += 1;
+            }
+        }
+
+        match count {
+            0 => Color::BLACK,
+            count => Color::new(
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+impl<'a> From<&'a image::RgbImage> for RgbFrame<'a> {
+    fn from(image: &'a image::RgbImage) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            data: image.as_raw(),
+        }
+    }
+}
+
+/// A side of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+/// The way a strip runs around the screen, seen from the front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Where the leds of a strip are around the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmbientConfig {
+    /// The edges in clockwise order with the number of leds on each. Leds along an edge are
+    /// sampled clockwise as well, so left to right along the top and top to bottom on the
+    /// right.
+    pub edges: Vec<(Edge, usize)>,
+    /// How far into the image every region reaches, as a fraction of its width or height.
+    pub depth: f32,
+    /// Index of the zone led the first region is shown on, the rest follow in `direction`.
+    pub start_offset: usize,
+    pub direction: Direction,
+    /// How much of the previous color every led keeps each frame, in `0.0..1.0`. Higher values
+    /// hide flicker at the cost of reacting slower.
+    pub smoothing: f32,
+}
+
+impl AmbientConfig {
+    pub fn new(edges: Vec<(Edge, usize)>) -> Self {
+        Self {
+            edges,
+            depth: 0.1,
+            start_offset: 0,
+            direction: Direction::Clockwise,
+            smoothing: 0.5,
+        }
+    }
+
+    /// A strip going all the way around the screen, starting at the bottom left corner.
+    pub fn surround(horizontal: usize, vertical: usize) -> Self {
+        Self::new(vec![
+            (Edge::Left, vertical),
+            (Edge::Top, horizontal),
+            (Edge::Right, vertical),
+            (Edge::Bottom, horizontal),
+        ])
+    }
+
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_start_offset(mut self, start_offset: usize) -> Self {
+        self.start_offset = start_offset;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Number of regions sampled from every frame.
+    pub fn len(&self) -> usize {
+        self.edges.iter().map(|(_, leds)| leds).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bias lighting following the edges of captured frames.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example(mut client: OpenRGBClient, captures: Vec<Vec<u8>>) -> OpenRGBResult<()> {
+/// client.get_device(0).await?;
+///
+/// let config = AmbientConfig::surround(30, 16).with_start_offset(8);
+/// let mut ambient = AmbientLight::new(config);
+///
+/// for capture in &captures {
+///     let frame = RgbFrame::new(1920, 1080, capture)?;
+///     client.update_ambient(0, 0, &mut ambient, &frame).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AmbientLight {
+    pub config: AmbientConfig,
+    smoothed: Vec<[f32; 3]>,
+}
+
+impl AmbientLight {
+    pub fn new(config: AmbientConfig) -> Self {
+        Self {
+            config,
+            smoothed: Vec::new(),
+        }
+    }
+
+    /// Forgets previous frames, so the next frame is shown without smoothing.
+    pub fn reset(&mut self) {
+        self.smoothed.clear();
+    }
+
+    /// Samples the average color of every region in clockwise order, without smoothing.
+    pub fn sample(&self, frame: &RgbFrame) -> Vec<Color> {
+        let (width, height) = (frame.width(), frame.height());
+        let depth_x = ((width as f32 * self.config.depth).ceil() as u32).clamp(1, width.max(1));
+        let depth_y = ((height as f32 * self.config.depth).ceil() as u32).clamp(1, height.max(1));
+
+        let mut colors = Vec::with_capacity(self.config.len());
+        for (edge, leds) in &self.config.edges {
+            let length = match edge {
+                Edge::Top | Edge::Bottom => width,
+                Edge::Left | Edge::Right => height,
+            };
+
+            for led in 0..*leds {
+                // Left and bottom are walked backwards when going clockwise.
+                let index = match edge {
+                    Edge::Top | Edge::Right => led,
+                    Edge::Bottom | Edge::Left => leds - 1 - led,
+                };
+                let start = (index * length as usize / leds) as u32;
+                let end = (((index + 1) * length as usize / leds) as u32).max(start + 1);
+
+                colors.push(match edge {
+                    Edge::Top => frame.average(start, 0, end, depth_y),
+                    Edge::Bottom => {
+                        frame.average(start, height.saturating_sub(depth_y), end, height)
+                    }
+                    Edge::Left => frame.average(0, start, depth_x, end),
+                    Edge::Right => frame.average(width.saturating_sub(depth_x), start, width, end),
+                });
+            }
+        }
+
+        colors
+    }
+
+    /// Samples the frame and blends it with the previous frames, returning the colors of every
+    /// region in clockwise order.
+    pub fn process(&mut self, frame: &RgbFrame) -> Vec<Color> {
+        let sampled = self.sample(frame);
+        let smoothing = self.config.smoothing.clamp(0.0, 1.0);
+
+        if self.smoothed.len() != sampled.len() {
+            self.smoothed = sampled
+                .iter()
+                .map(|color| [color.r as f32, color.g as f32, color.b as f32])
+                .collect();
+        } else {
+            for (smoothed, color) in self.smoothed.iter_mut().zip(&sampled) {
+                for (channel, target) in smoothed.iter_mut().zip(&[color.r, color.g, color.b]) {
+                    *channel = *channel * smoothing + *target as f32 * (1.0 - smoothing);
+                }
+            }
+        }
+
+        self.smoothed
+            .iter()
+            .map(|[r, g, b]| Color::new(r.round() as u8, g.round() as u8, b.round() as u8))
+            .collect()
+    }
+
+    /// Places region colors on the leds of a zone with `zone_leds` leds, following the
+    /// configured start offset and direction. Leds without a region are black.
+    pub fn to_zone_colors(&self, colors: &[Color], zone_leds: usize) -> Vec<Color> {
+        let mut zone = vec![Color::BLACK; zone_leds];
+
+        if zone_leds == 0 {
+            return zone;
+        }
+
+        for (region, color) in colors.iter().enumerate() {
+            // Going the other way round the first region stays first and the rest are reversed.
+            let position = match self.config.direction {
+                Direction::Clockwise => region,
+                Direction::CounterClockwise => (colors.len() - region) % colors.len(),
+            };
+
+            zone[(self.config.start_offset + position) % zone_leds] = *color;
+        }
+
+        zone
+    }
+}
+
+impl OpenRGBClient {
+    /// Processes the frame and shows it on a linear zone, see [`AmbientLight`].
+    pub async fn update_ambient(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        ambient: &mut AmbientLight,
+        frame: &RgbFrame<'_>,
+    ) -> OpenRGBResult<()> {
+        let zone_leds = self
+            .device(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
+            .zones
+            .get(zone_id)
+            .ok_or(OpenRGBError::InvalidId(zone_id))?
+            .leds_count as usize;
+
+        let colors = ambient.process(frame);
+        let colors = ambient.to_zone_colors(&colors, zone_leds);
+        self.update_zone_leds(device_id, zone_id, &colors).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame whose pixels get redder to the right and greener to the bottom.
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| (0..width).flat_map(move |x| vec![x as u8 * 50, y as u8 * 50, 0]))
+            .collect()
+    }
+
+    fn solid(width: u32, height: u32, value: u8) -> Vec<u8> {
+        vec![value; (width * height * 3) as usize]
+    }
+
+    #[test]
+    fn checks_frame_size() {
+        assert!(RgbFrame::new(2, 2, &[0; 12]).is_ok());
+        assert!(matches!(
+            RgbFrame::new(2, 2, &[0; 11]),
+            Err(OpenRGBError::InvalidFrameSize(12, 11))
+        ));
+    }
+
+    #[test]
+    fn samples_edges_clockwise_from_the_bottom_left() {
+        let data = gradient(4, 4);
+        let frame = RgbFrame::new(4, 4, &data).unwrap();
+        let ambient = AmbientLight::new(AmbientConfig::surround(2, 2).with_depth(0.25));
+
+        let expected: Vec<Color> = [
+            // Left, bottom to top.
+            (0, 125),
+            (0, 25),
+            // Top, left to right.
+            (25, 0),
+            (125, 0),
+            // Right, top to bottom.
+            (150, 25),
+            (150, 125),
+            // Bottom, right to left.
+            (125, 150),
+            (25, 150),
+        ]
+        .iter()
+        .map(|(r, g)| Color::new(*r, *g, 0))
+        .collect();
+        assert_eq!(ambient.sample(&frame), expected);
+    }
+
+    #[test]
+    fn splits_uneven_edges() {
+        let data = gradient(5, 3);
+        let frame = RgbFrame::new(5, 3, &data).unwrap();
+        let ambient = AmbientLight::new(AmbientConfig::new(vec![(Edge::Top, 3)]).with_depth(0.5));
+
+        // Columns 0, 1-2 and 3-4, two of the three rows deep.
+        assert_eq!(
+            ambient.sample(&frame),
+            vec![
+                Color::new(0, 25, 0),
+                Color::new(75, 25, 0),
+                Color::new(175, 25, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn smooths_until_reset() {
+        let (black, white) = (solid(2, 2, 0), solid(2, 2, 255));
+        let black = RgbFrame::new(2, 2, &black).unwrap();
+        let white = RgbFrame::new(2, 2, &white).unwrap();
+        let mut ambient = AmbientLight::new(AmbientConfig::new(vec![(Edge::Top, 1)]));
+
+        assert_eq!(ambient.process(&black), vec![Color::BLACK]);
+        assert_eq!(ambient.process(&white), vec![Color::new(128, 128, 128)]);
+        assert_eq!(ambient.process(&white), vec![Color::new(191, 191, 191)]);
+
+        ambient.reset();
+        assert_eq!(ambient.process(&black), vec![Color::BLACK]);
+    }
+
+    #[test]
+    fn places_regions_on_odd_zones() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE];
+        let config = AmbientConfig::new(vec![(Edge::Top, 3)]).with_start_offset(3);
+
+        let clockwise = AmbientLight::new(config.clone());
+        assert_eq!(
+            clockwise.to_zone_colors(&colors, 5),
+            vec![
+                Color::BLUE,
+                Color::BLACK,
+                Color::BLACK,
+                Color::RED,
+                Color::GREEN
+            ]
+        );
+
+        let counter_clockwise =
+            AmbientLight::new(config.with_direction(Direction::CounterClockwise));
+        assert_eq!(
+            counter_clockwise.to_zone_colors(&colors, 5),
+            vec![
+                Color::GREEN,
+                Color::BLACK,
+                Color::BLACK,
+                Color::RED,
+                Color::BLUE
+            ]
+        );
+
+        assert!(clockwise.to_zone_colors(&colors, 0).is_empty());
+    }
+}
@@ -0,0 +1,505 @@
+use crate::{
+    effects::{AudioEffect, FrameClock, LedLayout},
+    Color, OpenRGBClient, OpenRGBError, OpenRGBResult,
+};
+use hound::{SampleFormat, WavReader};
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::mpsc, task};
+
+const MIN_FREQUENCY: f32 = 20.0;
+const MAX_FREQUENCY: f32 = 20_000.0;
+// Kick drums and bass lines live below this, it's what we listen to for beats.
+const BASS_FREQUENCY: f32 = 200.0;
+// How often the analyzer assumes it's asked to analyze until told otherwise, most energy based
+// beat detectors compare against a second of history at this rate.
+const DEFAULT_ANALYSIS_RATE: f32 = 43.0;
+
+/// The result of analyzing the most recent samples.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioFrame {
+    /// Level of every band in `0.0..=1.0`, from the lowest to the highest frequency.
+    pub bands: Vec<f32>,
+    /// Root mean square of the samples in `0.0..=1.0`.
+    pub level: f32,
+    /// Whether the bass got noticeably louder than it was over the last second.
+    pub beat: bool,
+}
+
+/// Splits mono PCM samples into frequency bands using an FFT.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::{effects::*, *};
+/// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+/// client.get_device(0).await?;
+///
+/// let clip = AudioClip::open("song.wav")?;
+/// let mut analyzer = AudioAnalyzer::new(clip.sample_rate, 2048, 16);
+/// let mut bars = SpectrumBars::new(Color::GREEN, Color::RED);
+///
+/// client
+///     .play_audio(0, 0, &mut analyzer, clip.samples, &mut bars, 30.0)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AudioAnalyzer {
+    sample_rate: u32,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    samples: VecDeque<f32>,
+    bands: Vec<(f32, f32)>,
+    /// How much of the previous level a falling band keeps each analysis, in `0.0..1.0`.
+    pub smoothing: f32,
+    /// Level in decibels that is shown as an empty band.
+    pub floor_db: f32,
+    /// How many times louder than average the bass has to be to count as a beat.
+    pub beat_sensitivity: f32,
+    bass_history: VecDeque<f32>,
+    /// Number of analyses making up about a second, the history beats are compared against.
+    beat_history: usize,
+    frame: AudioFrame,
+}
+
+impl AudioAnalyzer {
+    /// Creates an analyzer for samples at the sample rate, with `band_count` logarithmically
+    /// spaced bands covering the audible spectrum.
+    ///
+    /// Larger FFT sizes resolve low frequencies better but react slower, 1024 or 2048 work well
+    /// for music at 44.1 kHz.
+    pub fn new(sample_rate: u32, fft_size: usize, band_count: usize) -> Self {
+        let fft_size = fft_size.max(2);
+        let max_frequency = MAX_FREQUENCY.min(sample_rate as f32 / 2.0);
+
+        Self {
+            sample_rate,
+            fft: FftPlanner::new().plan_fft_forward(fft_size),
+            // Hann window, so frequencies between bins don't leak into the whole spectrum.
+            window: (0..fft_size)
+                .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (fft_size - 1) as f32).cos())
+                .collect(),
+            samples: VecDeque::from(vec![0.0; fft_size]),
+            bands: Self::log_bands(band_count, MIN_FREQUENCY, max_frequency),
+            smoothing: 0.7,
+            floor_db: -60.0,
+            beat_sensitivity: 1.5,
+            bass_history: VecDeque::new(),
+            beat_history: DEFAULT_ANALYSIS_RATE as usize,
+            frame: AudioFrame {
+                bands: vec![0.0; band_count],
+                ..AudioFrame::default()
+            },
+        }
+    }
+
+    /// Replaces the bands with the given `(low, high)` frequency ranges in Hz.
+    pub fn with_bands(mut self, bands: Vec<(f32, f32)>) -> Self {
+        self.frame.bands = vec![0.0; bands.len()];
+        self.bands = bands;
+        self
+    }
+
+    /// Splits the range between the frequencies into bands of equal width on a log scale,
+    /// which is how we hear pitch.
+    pub fn log_bands(count: usize, min_frequency: f32, max_frequency: f32) -> Vec<(f32, f32)> {
+        let ratio = (max_frequency / min_frequency).powf(1.0 / count.max(1) as f32);
+
+        (0..count)
+            .map(|band| {
+                let low = min_frequency * ratio.powi(band as i32);
+                (low, low * ratio)
+            })
+            .collect()
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn bands(&self) -> &[(f32, f32)] {
+        &self.bands
+    }
+
+    /// Adds samples in `-1.0..=1.0`, only the last FFT size worth of samples are analyzed.
+    pub fn push_samples<I: IntoIterator<Item = f32>>(&mut self, samples: I) {
+        for sample in samples {
+            self.samples.pop_front();
+            self.samples.push_back(sample);
+        }
+    }
+
+    /// Sets how many times per second [`AudioAnalyzer::analyze`] is called, so beats are
+    /// compared against about a second of history. [`OpenRGBClient::play_audio`] sets it to its
+    /// frame rate.
+    pub fn set_analysis_rate(&mut self, analyses_per_second: f32) {
+        self.beat_history = (analyses_per_second.round() as usize).max(1);
+        while self.bass_history.len() > self.beat_history {
+            self.bass_history.pop_front();
+        }
+    }
+
+    /// Gets the result of the last analysis.
+    pub fn frame(&self) -> &AudioFrame {
+        &self.frame
+    }
+
+    /// Analyzes the most recent samples.
+    pub fn analyze(&mut self) -> &AudioFrame {
+        let mut buffer: Vec<Complex<f32>> = self
+            .samples
+            .iter()
+            .zip(&self.window)
+            .map(|(sample, weight)| Complex::new(sample * weight, 0.0))
+            .collect();
+        self.fft.process(&mut buffer);
+
+        // Scaled so a full scale sine wave has an amplitude of 1.
+        let scale = 2.0 / self.window.iter().sum::<f32>();
+        let bin_width = self.sample_rate as f32 / buffer.len() as f32;
+        let amplitudes: Vec<f32> = buffer[..buffer.len() / 2]
+            .iter()
+            .map(|bin| bin.norm() * scale)
+            .collect();
+
+        for ((low, high), level) in self.bands.iter().zip(self.frame.bands.iter_mut()) {
+            let bins = Self::bins(&amplitudes, bin_width, *low, *high);
+            let peak = bins.iter().cloned().fold(0.0, f32::max);
+            let db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+            let target = (1.0 - db / self.floor_db).clamp(0.0, 1.0);
+
+            *level = if target >= *level {
+                target
+            } else {
+                *level * self.smoothing + target * (1.0 - self.smoothing)
+            };
+        }
+
+        self.frame.level = (self
+            .samples
+            .iter()
+            .map(|sample| sample * sample)
+            .sum::<f32>()
+            / self.samples.len() as f32)
+            .sqrt()
+            .min(1.0);
+
+        let bass = Self::bins(&amplitudes, bin_width, 0.0, BASS_FREQUENCY)
+            .iter()
+            .sum::<f32>();
+        let average = self.bass_history.iter().sum::<f32>() / self.bass_history.len().max(1) as f32;
+        self.frame.beat = self.bass_history.len() == self.beat_history
+            && bass > average * self.beat_sensitivity
+            && bass > 0.01;
+
+        if self.bass_history.len() == self.beat_history {
+            self.bass_history.pop_front();
+        }
+        self.bass_history.push_back(bass);
+
+        &self.frame
+    }
+
+    /// Gets the amplitudes of the bins in the frequency range, always at least one bin so
+    /// narrow bands at the low end aren't empty.
+    fn bins(amplitudes: &[f32], bin_width: f32, low: f32, high: f32) -> &[f32] {
+        let last = amplitudes.len().saturating_sub(1);
+        let start = ((low / bin_width).round() as usize).min(last);
+        let end = ((high / bin_width).round() as usize).clamp(start + 1, amplitudes.len());
+
+        amplitudes.get(start..end).unwrap_or(&[])
+    }
+}
+
+/// Mono samples decoded from a WAV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioClip {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+impl AudioClip {
+    /// Decodes a WAV file, averaging its channels into one.
+    pub fn from_wav<R: Read>(reader: R) -> OpenRGBResult<Self> {
+        let reader = WavReader::new(reader)?;
+        let spec = reader.spec();
+
+        let samples = match spec.sample_format {
+            SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<Vec<_>, _>>()?,
+            SampleFormat::Int => {
+                let max = (1_i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 / max))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            samples: mix_down(&samples, spec.channels as usize),
+        })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> OpenRGBResult<Self> {
+        Self::from_wav(BufReader::new(File::open(path)?))
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f32(self.samples.len() as f32 / self.sample_rate.max(1) as f32)
+    }
+}
+
+fn mix_down(samples: &[f32], channels: usize) -> Vec<f32> {
+    samples
+        .chunks(channels.max(1))
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Encoding of raw PCM streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmFormat {
+    /// Signed 16 bit little endian integers.
+    S16Le,
+    /// 32 bit little endian floats.
+    F32Le,
+}
+
+impl PcmFormat {
+    fn sample_size(self) -> usize {
+        match self {
+            Self::S16Le => 2,
+            Self::F32Le => 4,
+        }
+    }
+}
+
+/// Reads raw interleaved PCM, such as piped to stdin by `parec` or `arecord`, as mono samples.
+///
+/// Reading blocks until the source has samples available and the iterator ends with the
+/// source or on the first read error. [`OpenRGBClient::play_audio`] reads it on a blocking
+/// thread, so this doesn't hold up other tasks.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// let samples = PcmReader::new(std::io::stdin(), PcmFormat::S16Le, 2);
+/// let analyzer = AudioAnalyzer::new(44_100, 1024, 12);
+/// ```
+#[derive(Debug)]
+pub struct PcmReader<R: Read> {
+    reader: R,
+    format: PcmFormat,
+    channels: usize,
+}
+
+impl<R: Read> PcmReader<R> {
+    pub fn new(reader: R, format: PcmFormat, channels: usize) -> Self {
+        Self {
+            reader,
+            format,
+            channels: channels.max(1),
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcmReader<R> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let mut frame = vec![0; self.format.sample_size() * self.channels];
+        self.reader.read_exact(&mut frame).ok()?;
+
+        let samples: Vec<f32> = frame
+            .chunks(self.format.sample_size())
+            .map(|bytes| match self.format {
+                PcmFormat::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+                PcmFormat::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            })
+            .collect();
+
+        Some(samples.iter().sum::<f32>() / samples.len() as f32)
+    }
+}
+
+/// Pulls chunks of samples from the iterator on a blocking thread, a couple of chunks ahead of
+/// the receiver. The thread stops once the samples run out or the receiver is dropped.
+fn read_chunks<I>(samples: I, chunk_size: usize) -> mpsc::Receiver<Vec<f32>>
+where
+    I: Iterator<Item = f32> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(2);
+
+    task::spawn_blocking(move || {
+        let mut samples = samples;
+        loop {
+            let chunk: Vec<f32> = samples.by_ref().take(chunk_size).collect();
+            if chunk.is_empty() || sender.blocking_send(chunk).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+impl OpenRGBClient {
+    /// Feeds the samples to the analyzer and renders the effect on a zone, sending `fps` frames
+    /// per second with [`OpenRGBClient::update_zone_leds`] until the samples run out.
+    ///
+    /// Samples are consumed at the analyzer's sample rate, so files play back in real time. They
+    /// are pulled from the iterator on a blocking thread, as sources such as a [`PcmReader`]
+    /// block until audio is available.
+    pub async fn play_audio<I, E>(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        analyzer: &mut AudioAnalyzer,
+        samples: I,
+        effect: &mut E,
+        fps: f32,
+    ) -> OpenRGBResult<()>
+    where
+        I: IntoIterator<Item = f32>,
+        I::IntoIter: Send + 'static,
+        E: AudioEffect + ?Sized,
+    {
+        let zone = self
+            .device(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
+            .zones
+            .get(zone_id)
+            .ok_or(OpenRGBError::InvalidId(zone_id))?;
+        let layout = LedLayout::for_zone(zone);
+        effect.configure(&layout);
+
+        let fps = fps.max(f32::EPSILON);
+        let samples_per_frame = (analyzer.sample_rate() as f32 / fps).ceil().max(1.0) as usize;
+        let mut chunks = read_chunks(samples.into_iter(), samples_per_frame);
+        analyzer.set_analysis_rate(fps);
+
+        let mut frame = vec![Color::BLACK; layout.len()];
+        let mut clock = FrameClock::new(fps, None);
+
+        while let (Some(chunk), Some(t)) = (chunks.recv().await, clock.next_frame().await) {
+            analyzer.push_samples(chunk);
+            effect.update(analyzer.analyze());
+            effect.render(t, &mut frame);
+
+            self.update_zone_leds(device_id, zone_id, &frame).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::tests::FakeServer, command::Command, effects::SpectrumBars, types::tests::device,
+    };
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn sine(frequency: f32, amplitude: f32, count: usize) -> impl Iterator<Item = f32> {
+        (0..count)
+            .map(move |i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+    }
+
+    #[test]
+    fn sine_peaks_in_its_band() {
+        // 20-112 Hz, 112-632 Hz, 632-3557 Hz and 3557-20000 Hz.
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 2048, 4);
+
+        for (frequency, band) in &[(1000.0, 2), (60.0, 0), (8000.0, 3)] {
+            analyzer.push_samples(sine(*frequency, 1.0, 2048));
+            let frame = analyzer.analyze().clone();
+
+            let loudest = (0..4)
+                .max_by(|a, b| frame.bands[*a].partial_cmp(&frame.bands[*b]).unwrap())
+                .unwrap();
+            assert_eq!(loudest, *band, "{} Hz: {:?}", frequency, frame.bands);
+            assert!(frame.bands[*band] > 0.9, "{:?}", frame.bands);
+            assert!((frame.level - 0.707).abs() < 0.01, "{}", frame.level);
+        }
+    }
+
+    #[test]
+    fn silence_is_empty() {
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 1024, 8);
+        analyzer.push_samples(vec![0.0; 1024]);
+
+        let frame = analyzer.analyze();
+        assert!(frame.bands.iter().all(|level| *level == 0.0));
+        assert_eq!(frame.level, 0.0);
+        assert!(!frame.beat);
+    }
+
+    #[test]
+    fn beats_need_a_second_of_history() {
+        let analyze = |rate: Option<f32>| {
+            let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 2048, 4);
+            if let Some(rate) = rate {
+                analyzer.set_analysis_rate(rate);
+            }
+
+            for _ in 0..4 {
+                analyzer.push_samples(sine(50.0, 0.05, 2048));
+                assert!(!analyzer.analyze().beat);
+            }
+            analyzer.push_samples(sine(50.0, 1.0, 2048));
+            analyzer.analyze().beat
+        };
+
+        assert!(analyze(Some(4.0)));
+        assert!(!analyze(None));
+    }
+
+    #[test]
+    fn reads_interleaved_pcm() {
+        let bytes: Vec<u8> = [16384_i16, -16384, 16384, 16384]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes().to_vec())
+            .collect();
+
+        let samples: Vec<f32> = PcmReader::new(bytes.as_slice(), PcmFormat::S16Le, 2).collect();
+        assert_eq!(samples, vec![0.0, 0.5]);
+    }
+
+    #[tokio::test]
+    async fn plays_a_frame_per_chunk() {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+        client.get_device(0).await.unwrap();
+        server.take_requests(&mut client).await;
+
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 1024, 3);
+        let samples: Vec<f32> = sine(1000.0, 1.0, 441 * 3 - 100).collect();
+        let mut bars = SpectrumBars::new(Color::GREEN, Color::RED);
+        client
+            .play_audio(0, 0, &mut analyzer, samples, &mut bars, 100.0)
+            .await
+            .unwrap();
+
+        let requests = server.take_requests(&mut client).await;
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|request| request.command == Command::UpdateZoneLeds as u32));
+    }
+}
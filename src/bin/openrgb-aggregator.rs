@@ -0,0 +1,73 @@
+use openrgb::*;
+use std::{net::SocketAddr, str::FromStr};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "openrgb-aggregator",
+    about = "Serves the devices of several OpenRGB servers as a single server"
+)]
+struct Options {
+    /// Address clients connect to.
+    #[structopt(long, default_value = "127.0.0.1:6743")]
+    listen: SocketAddr,
+    /// OpenRGB servers to combine as `[PREFIX=]HOST[:PORT]`, such as `desk=192.168.1.10`. The
+    /// prefix defaults to the host.
+    #[structopt(required = true)]
+    upstreams: Vec<UpstreamAddress>,
+}
+
+#[derive(Debug)]
+struct UpstreamAddress {
+    prefix: String,
+    address: String,
+}
+
+impl FromStr for UpstreamAddress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (prefix, address) = match value.find('=') {
+            Some(separator) => (&value[..separator], &value[separator + 1..]),
+            None => (value.split(':').next().unwrap_or(value), value),
+        };
+
+        if address.is_empty() {
+            return Err(format!("missing address in {}", value));
+        }
+
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:6742", address)
+        };
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            address,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    if let Err(error) = run(options).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(options: Options) -> OpenRGBResult<()> {
+    let mut aggregator = Aggregator::new();
+
+    for upstream in options.upstreams {
+        aggregator
+            .connect(upstream.prefix, upstream.address.as_str())
+            .await?;
+    }
+
+    let listener = tokio::net::TcpListener::bind(options.listen).await?;
+    aggregator.serve(listener).await
+}
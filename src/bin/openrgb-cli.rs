@@ -0,0 +1,296 @@
+use openrgb::*;
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "openrgb-cli",
+    about = "Controls RGB devices through an OpenRGB server"
+)]
+struct Options {
+    /// Address of the OpenRGB server.
+    #[structopt(long, default_value = "127.0.0.1")]
+    host: String,
+    #[structopt(long, default_value = "6742")]
+    port: u16,
+    /// Prints results as JSON.
+    #[structopt(long)]
+    json: bool,
+    #[structopt(subcommand)]
+    command: Subcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum Subcommand {
+    /// Lists all devices.
+    List,
+    /// Shows the modes, zones and leds of a device.
+    Info {
+        /// Index or exact name of the device.
+        device: String,
+    },
+    /// Sets all leds of a device or zone to a color.
+    SetColor {
+        device: String,
+        #[structopt(long)]
+        zone: Option<usize>,
+        /// A hex color such as `#FF8000` or a name such as `red`.
+        color: Color,
+    },
+    /// Switches a device to one of its modes.
+    SetMode {
+        device: String,
+        /// Name of the mode, ignoring case.
+        mode: String,
+        #[structopt(long)]
+        speed: Option<u32>,
+        /// Comma separated colors used by the mode.
+        #[structopt(long, use_delimiter = true)]
+        colors: Option<Vec<Color>>,
+    },
+    /// Changes the number of leds in a resizable zone.
+    ResizeZone {
+        device: String,
+        zone: usize,
+        size: u32,
+    },
+    /// Manages profiles saved in OpenRGB.
+    Profile(ProfileCommand),
+    /// Prints the packets of a recording made with `RecordingTransport`.
+    Decode { file: std::path::PathBuf },
+}
+
+#[derive(Debug, StructOpt)]
+enum ProfileCommand {
+    List,
+    Load { name: String },
+    Save { name: String },
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    if let Err(error) = run(options).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(options: Options) -> OpenRGBResult<()> {
+    // Recordings are decoded offline, without a server.
+    if let Subcommand::Decode { file } = &options.command {
+        let recording = Recording::open(file)?;
+        return recording.pretty_print(&mut std::io::stdout()).await;
+    }
+
+    let address = (options.host.as_str(), options.port);
+    let mut client = OpenRGBClient::connect(address, "openrgb-cli").await?;
+
+    match options.command {
+        Subcommand::List => {
+            let devices = client.get_all_devices().await?;
+
+            if options.json {
+                let devices: Vec<Value> = devices
+                    .iter()
+                    .enumerate()
+                    .map(|(id, device)| device_summary_json(id, device))
+                    .collect();
+                print_json(&Value::Array(devices));
+            } else {
+                for (id, device) in devices.iter().enumerate() {
+                    println!("{}: {} ({} leds)", id, device.name, device.leds.len());
+                }
+            }
+        }
+        Subcommand::Info { device } => {
+            let device_id = resolve(&mut client, &device).await?;
+            let device = client.get_device(device_id).await?;
+
+            if options.json {
+                print_json(&device_json(device_id, &device));
+            } else {
+                print_device(device_id, &device);
+            }
+        }
+        Subcommand::SetColor {
+            device,
+            zone,
+            color,
+        } => {
+            let device_id = resolve(&mut client, &device).await?;
+            let device = client.get_device(device_id).await?;
+            client.set_custom_mode(device_id).await?;
+
+            match zone {
+                Some(zone_id) => {
+                    let zone = device
+                        .zones
+                        .get(zone_id)
+                        .ok_or(OpenRGBError::InvalidId(zone_id))?;
+                    let colors = vec![color; zone.leds_count as usize];
+                    client.update_zone_leds(device_id, zone_id, &colors).await?;
+                }
+                None => {
+                    let colors = vec![color; device.colors.len()];
+                    client.update_leds(device_id, &colors).await?;
+                }
+            }
+        }
+        Subcommand::SetMode {
+            device,
+            mode,
+            speed,
+            colors,
+        } => {
+            let device_id = resolve(&mut client, &device).await?;
+            let device = client.get_device(device_id).await?;
+            let mut mode = device
+                .modes
+                .iter()
+                .find(|candidate| candidate.name.eq_ignore_ascii_case(&mode))
+                .cloned()
+                .ok_or(OpenRGBError::InvalidMode(mode))?;
+
+            if let Some(speed) = speed {
+                mode.speed = speed.clamp(
+                    mode.speed_min.min(mode.speed_max),
+                    mode.speed_max.max(mode.speed_min),
+                );
+            }
+
+            if let Some(colors) = colors {
+                mode.colors = colors;
+            }
+
+            client.update_mode(device_id, &mode).await?;
+        }
+        Subcommand::ResizeZone { device, zone, size } => {
+            let device_id = resolve(&mut client, &device).await?;
+            client.get_device(device_id).await?;
+            client.resize_zone(device_id, zone, size).await?;
+        }
+        Subcommand::Profile(ProfileCommand::List) => {
+            let profiles = client.get_profiles().await?;
+
+            if options.json {
+                print_json(&json!(profiles));
+            } else {
+                profiles.iter().for_each(|profile| println!("{}", profile));
+            }
+        }
+        Subcommand::Profile(ProfileCommand::Load { name }) => client.load_profile(name).await?,
+        Subcommand::Profile(ProfileCommand::Save { name }) => client.save_profile(name).await?,
+        Subcommand::Decode { .. } => unreachable!("recordings are decoded before connecting"),
+    }
+
+    Ok(())
+}
+
+/// Devices can be given by index or by name.
+async fn resolve(client: &mut OpenRGBClient, device: &str) -> OpenRGBResult<usize> {
+    let device_id = match device.parse() {
+        Ok(device_id) => device_id,
+        Err(_) => return client.resolve(&DeviceKey::new(device)).await,
+    };
+
+    if device_id >= client.get_device_count().await? {
+        return Err(OpenRGBError::InvalidId(device_id));
+    }
+
+    Ok(device_id)
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("json values always serialize")
+    );
+}
+
+fn print_device(device_id: usize, device: &OpenRGBDevice) {
+    println!("{}: {}", device_id, device.name);
+    println!("  description: {}", device.description);
+    println!("  version: {}", device.version);
+    println!("  serial: {}", device.serial);
+    println!("  location: {}", device.location);
+
+    println!("  modes:");
+    for (mode_id, mode) in device.modes.iter().enumerate() {
+        let active = if mode_id as i32 == device.active_mode {
+            " (active)"
+        } else {
+            ""
+        };
+        println!("    {}: {}{}", mode_id, mode.name, active);
+    }
+
+    println!("  zones:");
+    for (zone_id, zone) in device.zones.iter().enumerate() {
+        let matrix = match &zone.matrix_map {
+            Some(map) => format!(", {}x{} matrix", map.width, map.height),
+            None => String::new(),
+        };
+        println!(
+            "    {}: {} ({} leds{})",
+            zone_id, zone.name, zone.leds_count, matrix
+        );
+    }
+}
+
+fn device_summary_json(device_id: usize, device: &OpenRGBDevice) -> Value {
+    json!({
+        "id": device_id,
+        "name": device.name,
+        "serial": device.serial,
+        "location": device.location,
+        "leds": device.leds.len(),
+    })
+}
+
+fn device_json(device_id: usize, device: &OpenRGBDevice) -> Value {
+    let modes: Vec<Value> = device
+        .modes
+        .iter()
+        .map(|mode| {
+            json!({
+                "name": mode.name,
+                "speed": mode.speed,
+                "speed_min": mode.speed_min,
+                "speed_max": mode.speed_max,
+                "colors": mode.colors.iter().map(Color::to_hex).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    let zones: Vec<Value> = device
+        .zones
+        .iter()
+        .map(|zone| {
+            json!({
+                "name": zone.name,
+                "leds": zone.leds_count,
+                "leds_min": zone.leds_min,
+                "leds_max": zone.leds_max,
+                "matrix": zone.matrix_map.as_ref().map(|map| json!({
+                    "width": map.width,
+                    "height": map.height,
+                })),
+            })
+        })
+        .collect();
+
+    json!({
+        "id": device_id,
+        "name": device.name,
+        "description": device.description,
+        "version": device.version,
+        "serial": device.serial,
+        "location": device.location,
+        "active_mode": device.active_mode,
+        "modes": modes,
+        "zones": zones,
+        "leds": device.leds.iter().map(|led| led.name.clone()).collect::<Vec<_>>(),
+        "colors": device.colors.iter().map(Color::to_hex).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,49 @@
+use openrgb::*;
+use std::{net::SocketAddr, time::Duration};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "openrgb-http",
+    about = "Controls OpenRGB through a REST and WebSocket API"
+)]
+struct Options {
+    /// Address of the OpenRGB server.
+    #[structopt(long, default_value = "127.0.0.1:6742")]
+    openrgb: String,
+    /// Address the API is served on.
+    #[structopt(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Seconds between checks for changes to the device list.
+    #[structopt(long, default_value = "2")]
+    poll_interval: f64,
+    /// Origin of pages allowed to use the API, such as `http://dashboard.local:3000` or `*` for
+    /// every origin. Can be given many times.
+    #[structopt(long, number_of_values = 1)]
+    allow_origin: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    if let Err(error) = run(options).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(options: Options) -> OpenRGBResult<()> {
+    let client = OpenRGBClient::connect(options.openrgb.as_str(), "openrgb-http").await?;
+    let listener = tokio::net::TcpListener::bind(options.listen).await?;
+
+    // Polling in a tight loop would starve the API of the client.
+    let poll_interval = Duration::from_secs_f64(options.poll_interval.max(0.1));
+    let mut server = HttpServer::new(client).with_poll_interval(poll_interval);
+
+    for origin in options.allow_origin {
+        server = server.with_allowed_origin(origin);
+    }
+
+    server.serve(listener).await
+}
@@ -0,0 +1,53 @@
+use openrgb::*;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "openrgb-mqtt",
+    about = "Publishes OpenRGB devices to Home Assistant over MQTT"
+)]
+struct Options {
+    /// Address of the OpenRGB server.
+    #[structopt(long, default_value = "127.0.0.1:6742")]
+    openrgb: String,
+    #[structopt(long, default_value = "127.0.0.1")]
+    mqtt_host: String,
+    #[structopt(long, default_value = "1883")]
+    mqtt_port: u16,
+    #[structopt(long)]
+    username: Option<String>,
+    #[structopt(long, env = "MQTT_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+    #[structopt(long, default_value = "openrgb")]
+    client_id: String,
+    /// Prefix of the state, command and availability topics.
+    #[structopt(long, default_value = "openrgb")]
+    base_topic: String,
+    #[structopt(long, default_value = "homeassistant")]
+    discovery_prefix: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    if let Err(error) = run(options).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(options: Options) -> OpenRGBResult<()> {
+    let client = OpenRGBClient::connect(options.openrgb.as_str(), "openrgb-mqtt").await?;
+
+    let mut config = MqttConfig::new(options.mqtt_host, options.mqtt_port)
+        .with_client_id(options.client_id)
+        .with_base_topic(options.base_topic)
+        .with_discovery_prefix(options.discovery_prefix);
+
+    if let Some(username) = options.username {
+        config = config.with_credentials(username, options.password);
+    }
+
+    MqttBridge::new(client, config).run().await
+}
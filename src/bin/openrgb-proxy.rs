@@ -0,0 +1,75 @@
+use openrgb::*;
+use std::{net::SocketAddr, str::FromStr, time::Duration};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "openrgb-proxy",
+    about = "Shares an OpenRGB server between clients, arbitrating led writes by priority"
+)]
+struct Options {
+    /// Address clients connect to.
+    #[structopt(long, default_value = "127.0.0.1:6743")]
+    listen: SocketAddr,
+    /// Address of the OpenRGB server.
+    #[structopt(long, default_value = "127.0.0.1:6742")]
+    upstream: SocketAddr,
+    /// Priority of a client by name, such as `--priority "Ambient=10"`. Can be given many times.
+    #[structopt(long, number_of_values = 1)]
+    priority: Vec<ClientPriority>,
+    /// Priority of clients not given with `--priority`.
+    #[structopt(long, default_value = "0", allow_hyphen_values = true)]
+    default_priority: i32,
+    /// Seconds without writes after which a client's leds are released to lower priorities.
+    #[structopt(long)]
+    release_after: Option<f64>,
+}
+
+#[derive(Debug)]
+struct ClientPriority {
+    name: String,
+    priority: i32,
+}
+
+impl FromStr for ClientPriority {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let separator = value
+            .rfind('=')
+            .ok_or_else(|| format!("expected NAME=PRIORITY, found {}", value))?;
+        let priority = value[separator + 1..]
+            .parse()
+            .map_err(|_| format!("invalid priority in {}", value))?;
+
+        Ok(Self {
+            name: value[..separator].to_string(),
+            priority,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Options::from_args();
+
+    if let Err(error) = run(options).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(options: Options) -> OpenRGBResult<()> {
+    let mut config = ProxyConfig::new().with_default_priority(options.default_priority);
+
+    for client in options.priority {
+        config = config.with_priority(client.name, client.priority);
+    }
+
+    if let Some(seconds) = options.release_after {
+        config = config.with_release_timeout(Duration::from_secs_f64(seconds.max(0.0)));
+    }
+
+    let listener = tokio::net::TcpListener::bind(options.listen).await?;
+    Proxy::new(options.upstream, config).serve(listener).await
+}
@@ -1,30 +1,63 @@
 use crate::command::Command;
 use crate::{
+    diff::{plan_updates, LedUpdate},
     network::{connection::OpenRGBConnection, packet::*},
-    types::{OpenRGBColor, OpenRGBDevice},
-    OpenRGBError, OpenRGBMode, OpenRGBResult,
+    types::{OpenRGBDevice, OpenRGBMatrixMap},
+    Color, ColorTransform, DeviceKey, MatrixFrame, OpenRGBError, OpenRGBMode, OpenRGBResult,
 };
 use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
 use tokio::{
-    io::AsyncWriteExt,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
     net::{TcpStream, ToSocketAddrs},
 };
 
+/// A connection to an OpenRGB server, usually a [`TcpStream`].
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> Transport for T {}
+
+/// Colors kept for a device's leds, tagged with the device so they can be dropped if its id
+/// ends up referring to another device.
+struct LedBuffer {
+    key: DeviceKey,
+    colors: Vec<Color>,
+}
+
 pub struct OpenRGBClient {
-    connection: TcpStream,
+    connection: Box<dyn Transport>,
     name: String,
-    devices: Vec<OpenRGBDevice>,
+    devices: BTreeMap<usize, OpenRGBDevice>,
+    transforms: HashMap<String, ColorTransform>,
+    /// The last colors sent to each device's leds before their color transform, where the
+    /// cached devices hold the transformed colors the leds actually show.
+    sent: HashMap<usize, LedBuffer>,
+    staged: HashMap<usize, LedBuffer>,
+    devices_changed: bool,
 }
 
 impl OpenRGBClient {
     pub async fn connect<A: ToSocketAddrs, S: Into<String>>(
         address: A,
         name: S,
+    ) -> OpenRGBResult<Self> {
+        Self::with_transport(TcpStream::connect(address).await?, name).await
+    }
+
+    /// Creates a client talking to OpenRGB over an already established connection, such as a
+    /// [`crate::RecordingTransport`].
+    pub async fn with_transport<T: Transport + 'static, S: Into<String>>(
+        transport: T,
+        name: S,
     ) -> OpenRGBResult<Self> {
         let mut client = Self {
-            connection: TcpStream::connect(address).await?,
+            connection: Box::new(transport),
             name: name.into(),
-            devices: vec![],
+            devices: BTreeMap::new(),
+            transforms: HashMap::new(),
+            sent: HashMap::new(),
+            staged: HashMap::new(),
+            devices_changed: false,
         };
 
         client.send_name().await.map(|_| client)
@@ -40,7 +73,7 @@ impl OpenRGBClient {
     /// Gets the number of devices that OpenRGB can control.
     pub async fn get_device_count(&mut self) -> OpenRGBResult<usize> {
         Self::send_command(&mut self.connection, Command::RequestControllerCount, None).await?;
-        let count = match Self::read_packet(&mut self.connection).await? {
+        let count = match self.read_response().await? {
             OpenRGBPackets::RequestControllerCount(packet) => packet.count as usize,
             _ => return Err(OpenRGBError::UnexpectedPacket),
         };
@@ -50,41 +83,150 @@ impl OpenRGBClient {
     /// Requests device data from OpenRGB, this is just a representation of the device at the time it was requested.
     /// Changes to the device will not be tracked in this value.
     pub async fn get_device(&mut self, device_id: usize) -> OpenRGBResult<OpenRGBDevice> {
+        self.refresh_device(device_id).await.cloned()
+    }
+
+    /// Requests device data from OpenRGB and replaces the cached copy of the device with it.
+    pub async fn refresh_device(&mut self, device_id: usize) -> OpenRGBResult<&OpenRGBDevice> {
         Self::send_command(
             &mut self.connection,
             Command::RequestControllerData,
             Some(device_id),
         )
         .await?;
-        let device = match Self::read_packet(&mut self.connection).await? {
+        let device = match self.read_response().await? {
             OpenRGBPackets::RequestControllerData(packet) => packet.device,
             _ => return Err(OpenRGBError::UnexpectedPacket),
         };
 
         // Store a copy of the device so we can use device info for later calls.
-        if self.devices.len() <= device_id as usize {
-            let mut devices = Vec::new();
-            let mut inserted_new_device = false;
-
-            for (id, old_device) in self.devices.iter().enumerate() {
-                devices.push(if id == device_id {
-                    inserted_new_device = true;
-                    device.clone()
-                } else {
-                    old_device.clone()
-                });
-            }
+        self.devices.insert(device_id, device);
+        self.check_buffers(device_id);
+        Ok(&self.devices[&device_id])
+    }
+
+    /// Drops all cached devices and requests every device OpenRGB can control.
+    pub async fn refresh_all(&mut self) -> OpenRGBResult<()> {
+        self.get_all_devices().await.map(|_| ())
+    }
 
-            if !inserted_new_device {
-                devices.push(device.clone())
+    /// Requests every device OpenRGB can control, replacing the cached devices.
+    ///
+    /// All requests are sent before any response is read, so this only takes a single round-trip
+    /// regardless of the number of devices.
+    pub async fn get_all_devices(&mut self) -> OpenRGBResult<Vec<OpenRGBDevice>> {
+        let count = self.get_device_count().await?;
+
+        for device_id in 0..count {
+            Self::send_command(
+                &mut self.connection,
+                Command::RequestControllerData,
+                Some(device_id),
+            )
+            .await?;
+        }
+
+        let mut devices = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.read_response().await? {
+                OpenRGBPackets::RequestControllerData(packet) => devices.push(packet.device),
+                _ => return Err(OpenRGBError::UnexpectedPacket),
             }
+        }
 
-            self.devices = devices;
-        } else {
-            self.devices.insert(device_id as usize, device.clone());
+        self.devices = devices.iter().cloned().enumerate().collect();
+        self.sent.retain(|device_id, _| *device_id < count);
+        self.staged.retain(|device_id, _| *device_id < count);
+        for device_id in 0..count {
+            self.check_buffers(device_id);
+        }
+
+        Ok(devices)
+    }
+
+    /// Gets the cached copy of the device, requested by an earlier call to [`OpenRGBClient::get_device`],
+    /// [`OpenRGBClient::refresh_device`] or [`OpenRGBClient::refresh_all`].
+    pub fn device(&self, device_id: usize) -> Option<&OpenRGBDevice> {
+        self.devices.get(&device_id)
+    }
+
+    /// Gets all cached devices by their id.
+    ///
+    /// The cache is dropped whenever OpenRGB notifies us that its device list changed, as the ids
+    /// may no longer refer to the same devices.
+    pub fn devices(&self) -> &BTreeMap<usize, OpenRGBDevice> {
+        &self.devices
+    }
+
+    /// Finds the id of the device identified by the key.
+    ///
+    /// Devices that moved to a different location are still found by their serial, if the key has
+    /// one. If no cached device matches, the device list is requested again before giving up.
+    pub async fn resolve(&mut self, key: &DeviceKey) -> OpenRGBResult<usize> {
+        if let Some(result) = self.find_device(key) {
+            return result;
         }
 
-        Ok(device)
+        self.refresh_all().await?;
+        self.find_device(key)
+            .unwrap_or_else(|| Err(OpenRGBError::UnknownDevice(key.to_string())))
+    }
+
+    /// Gets the key identifying the cached device.
+    pub fn device_key(&self, device_id: usize) -> Option<DeviceKey> {
+        self.devices.get(&device_id).map(DeviceKey::from)
+    }
+
+    fn find_device(&self, key: &DeviceKey) -> Option<OpenRGBResult<usize>> {
+        let find = |matches: &dyn Fn(&OpenRGBDevice) -> bool| {
+            let ids: Vec<usize> = self
+                .devices
+                .iter()
+                .filter(|(_, device)| matches(device))
+                .map(|(id, _)| *id)
+                .collect();
+
+            match ids.as_slice() {
+                [] => None,
+                [id] => Some(Ok(*id)),
+                _ => Some(Err(OpenRGBError::AmbiguousDevice(key.to_string()))),
+            }
+        };
+
+        find(&|device| key.matches(device)).or_else(|| {
+            key.serial
+                .as_ref()
+                .and_then(|_| find(&|device| key.matches_loosely(device)))
+        })
+    }
+
+    /// Drops all cached devices.
+    ///
+    /// Colors sent or staged for them are kept for devices that are still at the same id once
+    /// they are requested again.
+    pub fn invalidate_devices(&mut self) {
+        self.devices.clear();
+    }
+
+    /// Returns `true` once after OpenRGB reported that its devices changed.
+    ///
+    /// Reports are only read while waiting for the answer to a request, a cheap request such as
+    /// [`OpenRGBClient::get_device_count`] picks up any that arrived in the meantime.
+    pub fn take_devices_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.devices_changed, false)
+    }
+
+    /// Reads the response to a request, handling any notifications OpenRGB sent before it.
+    async fn read_response(&mut self) -> OpenRGBResult<OpenRGBPackets> {
+        loop {
+            match Self::read_packet(&mut self.connection).await? {
+                OpenRGBPackets::Command(Command::DeviceListUpdated) => {
+                    self.invalidate_devices();
+                    self.devices_changed = true;
+                }
+                packet => return Ok(packet),
+            }
+        }
     }
 
     /// Sets the device into the "custom" mode, which will often be mode `0`.
@@ -107,20 +249,35 @@ impl OpenRGBClient {
     /// # async fn test() -> OpenRGBResult<()> {
     /// let client = OpenRGBClient::connect("0.0.0.0:6742", "Example").await?;
     /// let device = client.get_device(0).await?;
-    /// let colors = vec![(255, 0, 0); device.colors.len()];
+    /// let colors = vec![Color::RED; device.colors.len()];
     /// client.update_leds(0, &colors).await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn update_leds(
+    pub async fn update_leds(&mut self, device_id: usize, colors: &[Color]) -> OpenRGBResult<()> {
+        self.send_leds(device_id, colors, true).await
+    }
+
+    /// Sets all leds of the device to colors as they should be shown, without applying its color
+    /// transform, such as colors captured in a scene.
+    pub(crate) async fn update_leds_untransformed(
         &mut self,
         device_id: usize,
-        colors: &[OpenRGBColor],
+        colors: &[Color],
+    ) -> OpenRGBResult<()> {
+        self.send_leds(device_id, colors, false).await
+    }
+
+    async fn send_leds(
+        &mut self,
+        device_id: usize,
+        colors: &[Color],
+        transform: bool,
     ) -> OpenRGBResult<()> {
         let device = self
             .devices
-            .get(device_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(device_id))?;
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
 
         if colors.len() > device.colors.len() {
             return Err(OpenRGBError::InvalidColorAmount(
@@ -129,8 +286,20 @@ impl OpenRGBClient {
             ));
         }
 
-        let packet = UpdateLedsPacket::new(colors.to_vec());
-        Self::send_packet(&mut self.connection, packet, Some(device_id)).await
+        let shown = if transform {
+            self.transform_colors(device_id, colors)
+        } else {
+            colors.to_vec()
+        };
+        let packet = UpdateLedsPacket::new(shown.clone());
+        Self::send_packet(&mut self.connection, packet, Some(device_id)).await?;
+
+        if transform {
+            self.store_colors(device_id, 0, colors, &shown);
+        } else {
+            self.store_shown_colors(device_id, &shown);
+        }
+        Ok(())
     }
 
     /// Sets all leds in the zone to their provided color.
@@ -138,15 +307,15 @@ impl OpenRGBClient {
         &mut self,
         device_id: usize,
         zone_id: usize,
-        colors: &[OpenRGBColor],
+        colors: &[Color],
     ) -> OpenRGBResult<()> {
         let zone = self
             .devices
-            .get(device_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(device_id))?
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
             .zones
             .get(zone_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(zone_id))?;
+            .ok_or(OpenRGBError::InvalidId(zone_id))?;
 
         if colors.len() > zone.leds_count as usize {
             return Err(OpenRGBError::InvalidColorAmount(
@@ -155,8 +324,48 @@ impl OpenRGBClient {
             ));
         }
 
-        let packet = UpdateZoneLedsPacket::new(zone_id, colors.to_vec());
-        Self::send_packet(&mut self.connection, packet, Some(device_id)).await
+        let shown = self.transform_colors(device_id, colors);
+        let packet = UpdateZoneLedsPacket::new(zone_id, shown.clone());
+        Self::send_packet(&mut self.connection, packet, Some(device_id)).await?;
+
+        let offset = self.devices[&device_id]
+            .zone_led_offset(zone_id)
+            .unwrap_or(0);
+        self.store_colors(device_id, offset, colors, &shown);
+        Ok(())
+    }
+
+    /// Gets the matrix map of a cached device's zone.
+    pub fn matrix_map(&self, device_id: usize, zone_id: usize) -> OpenRGBResult<&OpenRGBMatrixMap> {
+        self.devices
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
+            .zones
+            .get(zone_id)
+            .ok_or(OpenRGBError::InvalidId(zone_id))?
+            .matrix_map
+            .as_ref()
+            .ok_or(OpenRGBError::NotAMatrix(zone_id))
+    }
+
+    /// Sets the leds of a matrix zone to the colors painted in the frame, leds whose cell hasn't
+    /// been painted keep their current color.
+    pub async fn update_matrix(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        frame: &MatrixFrame,
+    ) -> OpenRGBResult<()> {
+        let map = self.matrix_map(device_id, zone_id)?;
+        let device = &self.devices[&device_id];
+        let colors = self.colors(device_id).unwrap_or(&[]);
+
+        let offset = device.zone_led_offset(zone_id).unwrap_or(0);
+        let end = (offset + device.zones[zone_id].leds_count as usize).min(colors.len());
+        let current = colors.get(offset..end).unwrap_or(&[]);
+
+        let colors = frame.to_zone_colors(map, current);
+        self.update_zone_leds(device_id, zone_id, &colors).await
     }
 
     /// Updates the color of a single led.
@@ -164,27 +373,48 @@ impl OpenRGBClient {
         &mut self,
         device_id: usize,
         led_id: usize,
-        color: OpenRGBColor,
+        color: Color,
     ) -> OpenRGBResult<()> {
         let device = self
             .devices
-            .get(device_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(device_id))?;
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
 
         if led_id >= device.leds.len() {
             return Err(OpenRGBError::InvalidId(led_id));
         }
 
-        let packet = UpdateSingleLedPacket::new(led_id, color);
-        Self::send_packet(&mut self.connection, packet, Some(device_id)).await
+        let shown = self.transform_colors(device_id, &[color]);
+        let packet = UpdateSingleLedPacket::new(led_id, shown[0]);
+        Self::send_packet(&mut self.connection, packet, Some(device_id)).await?;
+        self.store_colors(device_id, led_id, &[color], &shown);
+        Ok(())
     }
 
     /// Updates the mode that the device is using and then switch to it.
     pub async fn update_mode(&mut self, device_id: usize, mode: &OpenRGBMode) -> OpenRGBResult<()> {
+        self.send_mode(device_id, mode, true).await
+    }
+
+    /// Updates the mode of the device without applying its color transform to the mode's colors.
+    pub(crate) async fn update_mode_untransformed(
+        &mut self,
+        device_id: usize,
+        mode: &OpenRGBMode,
+    ) -> OpenRGBResult<()> {
+        self.send_mode(device_id, mode, false).await
+    }
+
+    async fn send_mode(
+        &mut self,
+        device_id: usize,
+        mode: &OpenRGBMode,
+        transform: bool,
+    ) -> OpenRGBResult<()> {
         let device = self
             .devices
-            .get(device_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(device_id))?;
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
         let mode_id = device
             .modes
             .iter()
@@ -194,7 +424,12 @@ impl OpenRGBClient {
             .next()
             .ok_or_else(|| OpenRGBError::InvalidMode(mode.name.clone()))?;
 
-        let packet = UpdateModePacket::new(mode_id, mode.clone());
+        let mut mode = mode.clone();
+        if transform {
+            mode.colors = self.transform_colors(device_id, &mode.colors);
+        }
+
+        let packet = UpdateModePacket::new(mode_id, mode);
         Self::send_packet(&mut self.connection, packet, Some(device_id)).await
     }
 
@@ -205,16 +440,522 @@ impl OpenRGBClient {
         new_size: u32,
     ) -> OpenRGBResult<()> {
         self.devices
-            .get(device_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(device_id))?
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
             .zones
             .get(zone_id)
-            .ok_or_else(|| OpenRGBError::InvalidId(zone_id))?;
+            .ok_or(OpenRGBError::InvalidId(zone_id))?;
 
         let packet = ResizeZonePacket::new(zone_id, new_size);
         Self::send_packet(&mut self.connection, packet, Some(device_id)).await
     }
+
+    /// Gets the names of the profiles saved in OpenRGB.
+    pub async fn get_profiles(&mut self) -> OpenRGBResult<Vec<String>> {
+        Self::send_command(&mut self.connection, Command::RequestProfileList, None).await?;
+        match self.read_response().await? {
+            OpenRGBPackets::RequestProfileList(packet) => Ok(packet.profiles),
+            // OpenRGB sends an empty packet if there are no profiles.
+            OpenRGBPackets::Command(Command::RequestProfileList) => Ok(Vec::new()),
+            _ => Err(OpenRGBError::UnexpectedPacket),
+        }
+    }
+
+    /// Saves the current state of every device as a profile, replacing any profile with the
+    /// same name.
+    pub async fn save_profile<S: Into<String>>(&mut self, name: S) -> OpenRGBResult<()> {
+        let packet = ProfilePacket::save(name.into());
+        Self::send_packet(&mut self.connection, packet, None).await
+    }
+
+    /// Applies a saved profile.
+    ///
+    /// As this changes the modes and colors of any number of devices, all cached devices are
+    /// dropped.
+    pub async fn load_profile<S: Into<String>>(&mut self, name: S) -> OpenRGBResult<()> {
+        let packet = ProfilePacket::load(name.into());
+        Self::send_packet(&mut self.connection, packet, None).await?;
+        self.invalidate_devices();
+        Ok(())
+    }
+
+    pub async fn delete_profile<S: Into<String>>(&mut self, name: S) -> OpenRGBResult<()> {
+        let packet = ProfilePacket::delete(name.into());
+        Self::send_packet(&mut self.connection, packet, None).await
+    }
+
+    /// Gets the last colors sent to the device's leds, before the device's color transform.
+    ///
+    /// This starts out as the colors reported by [`OpenRGBClient::get_device`] and is kept up to
+    /// date by every led update made through this client. If the device is requested again and
+    /// no longer shows these colors, such as when another client changed them, it goes back to
+    /// the reported colors.
+    pub fn colors(&self, device_id: usize) -> Option<&[Color]> {
+        let device = self.devices.get(&device_id)?;
+        let colors = match self.sent.get(&device_id) {
+            Some(sent) => &sent.colors,
+            None => &device.colors,
+        };
+        Some(colors.as_slice())
+    }
+
+    /// Gets a buffer of the device's led colors that can be modified and later sent with
+    /// [`OpenRGBClient::commit`].
+    ///
+    /// ## Example:
+    /// ```rust
+    /// # use openrgb::*;
+    /// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+    /// client.get_device(0).await?;
+    /// client.staged_colors(0)?[3] = Color::RED;
+    /// client.commit(0).await?; // Only sends an update for led 3.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn staged_colors(&mut self, device_id: usize) -> OpenRGBResult<&mut [Color]> {
+        let device = self
+            .devices
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+
+        if !self.staged.contains_key(&device_id) {
+            let buffer = LedBuffer {
+                key: DeviceKey::from(device),
+                colors: self.colors(device_id).unwrap_or(&[]).to_vec(),
+            };
+            self.staged.insert(device_id, buffer);
+        }
+
+        Ok(self
+            .staged
+            .get_mut(&device_id)
+            .unwrap()
+            .colors
+            .as_mut_slice())
+    }
+
+    /// Sends the staged colors of the device using the smallest set of update packets that
+    /// brings the device from its current colors to the staged ones.
+    ///
+    /// If an update fails the colors stay staged, committing again sends what is still missing.
+    pub async fn commit(&mut self, device_id: usize) -> OpenRGBResult<()> {
+        if !self.staged.contains_key(&device_id) {
+            return Ok(());
+        }
+
+        let device = self
+            .devices
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+        let colors = self.colors(device_id).unwrap_or(&[]);
+        let updates = plan_updates(&device.zones, colors, &self.staged[&device_id].colors);
+
+        // The colors stay staged until they were all sent, so a failed commit can be retried.
+        for update in updates {
+            match update {
+                LedUpdate::Single { led_id, color } => {
+                    self.update_single_led(device_id, led_id, color).await?
+                }
+                LedUpdate::Zone { zone_id, colors } => {
+                    self.update_zone_leds(device_id, zone_id, &colors).await?
+                }
+                LedUpdate::All { colors } => self.update_leds(device_id, &colors).await?,
+            }
+        }
+
+        self.staged.remove(&device_id);
+        Ok(())
+    }
+
+    /// Commits the staged colors of every device.
+    ///
+    /// If the device list changed since the colors were staged, it is requested again first and
+    /// colors staged for devices that are no longer at the same id are dropped.
+    pub async fn commit_all(&mut self) -> OpenRGBResult<()> {
+        if self.staged.keys().any(|id| !self.devices.contains_key(id)) {
+            self.refresh_all().await?;
+        }
+
+        let mut device_ids: Vec<usize> = self.staged.keys().copied().collect();
+        device_ids.sort_unstable();
+
+        for device_id in device_ids {
+            self.commit(device_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the colors sent to the device's leds both as they were given and as shown after
+    /// the color transform.
+    fn store_colors(&mut self, device_id: usize, offset: usize, colors: &[Color], shown: &[Color]) {
+        let device = match self.devices.get_mut(&device_id) {
+            Some(device) => device,
+            None => return,
+        };
+
+        let sent = self.sent.entry(device_id).or_insert_with(|| LedBuffer {
+            key: DeviceKey::from(&*device),
+            colors: device.colors.clone(),
+        });
+
+        for (led, color) in sent.colors.iter_mut().skip(offset).zip(colors) {
+            *led = *color;
+        }
+        for (led, color) in device.colors.iter_mut().skip(offset).zip(shown) {
+            *led = *color;
+        }
+    }
+
+    /// Stores colors sent to all of the device's leds as they are shown. As the colors they were
+    /// given as before the color transform aren't known, the device's shown colors are used for
+    /// them from now on.
+    fn store_shown_colors(&mut self, device_id: usize, shown: &[Color]) {
+        if let Some(device) = self.devices.get_mut(&device_id) {
+            for (led, color) in device.colors.iter_mut().zip(shown) {
+                *led = *color;
+            }
+        }
+        self.sent.remove(&device_id);
+    }
+
+    /// Drops the colors kept for a freshly requested device if its id now refers to another
+    /// device, and the colors sent to it if it doesn't show them anymore.
+    fn check_buffers(&mut self, device_id: usize) {
+        let device = match self.devices.get(&device_id) {
+            Some(device) => device,
+            None => return,
+        };
+
+        let moved = |buffer: &LedBuffer| !buffer.key.matches(device);
+        let staged_moved = match self.staged.get(&device_id) {
+            Some(staged) => moved(staged),
+            None => false,
+        };
+        let sent_stale = match self.sent.get(&device_id) {
+            Some(sent) => {
+                moved(sent) || self.transform_colors(device_id, &sent.colors) != device.colors
+            }
+            None => false,
+        };
+
+        if staged_moved {
+            self.staged.remove(&device_id);
+        }
+        if sent_stale {
+            self.sent.remove(&device_id);
+        }
+    }
+
+    /// Sets the color transform applied to every color sent to the device.
+    ///
+    /// Transforms are stored by the device's serial so they stay attached to the same hardware
+    /// when its id changes.
+    pub fn set_color_transform(
+        &mut self,
+        device_id: usize,
+        transform: ColorTransform,
+    ) -> OpenRGBResult<()> {
+        let device = self
+            .devices
+            .get(&device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+
+        self.transforms
+            .insert(ColorTransform::device_key(device), transform);
+        Ok(())
+    }
+
+    /// Gets the color transform of the device, if one has been set.
+    pub fn color_transform(&self, device_id: usize) -> Option<&ColorTransform> {
+        let device = self.devices.get(&device_id)?;
+        self.transforms.get(&ColorTransform::device_key(device))
+    }
+
+    /// Removes the color transform of the device, returning it if one was set.
+    pub fn clear_color_transform(&mut self, device_id: usize) -> Option<ColorTransform> {
+        let device = self.devices.get(&device_id)?;
+        self.transforms.remove(&ColorTransform::device_key(device))
+    }
+
+    /// All configured color transforms keyed by [`ColorTransform::device_key`], useful for
+    /// persisting them between sessions.
+    pub fn color_transforms(&self) -> &HashMap<String, ColorTransform> {
+        &self.transforms
+    }
+
+    /// Replaces all configured color transforms, e.g. with ones loaded from a previous session.
+    pub fn set_color_transforms(&mut self, transforms: HashMap<String, ColorTransform>) {
+        self.transforms = transforms;
+    }
+
+    /// Writes all configured color transforms keyed by [`ColorTransform::device_key`], in any
+    /// format serde supports.
+    ///
+    /// As transforms are keyed by the device's serial, loading them in a later session with
+    /// [`OpenRGBClient::load_color_transforms`] attaches them to the same hardware even if the
+    /// device ids changed.
+    #[cfg(feature = "serde")]
+    pub fn save_color_transforms<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let transforms: BTreeMap<&String, &ColorTransform> = self.transforms.iter().collect();
+        serde::Serialize::serialize(&transforms, serializer)
+    }
+
+    /// Reads color transforms written by [`OpenRGBClient::save_color_transforms`], replacing
+    /// any configured transform of the same devices.
+    #[cfg(feature = "serde")]
+    pub fn load_color_transforms<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        let transforms: HashMap<String, ColorTransform> =
+            serde::Deserialize::deserialize(deserializer)?;
+        self.transforms.extend(transforms);
+        Ok(())
+    }
+
+    /// Gives up the client for its connection, to pass packets on without understanding them.
+    #[cfg(feature = "server")]
+    pub(crate) fn into_transport(self) -> Box<dyn Transport> {
+        self.connection
+    }
+
+    fn transform_colors(&self, device_id: usize, colors: &[Color]) -> Vec<Color> {
+        match self.color_transform(device_id) {
+            Some(transform) => transform.apply_all(colors),
+            None => colors.to_vec(),
+        }
+    }
 }
 
 #[async_trait]
 impl OpenRGBConnection for OpenRGBClient {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        io::OpenRGBSendable,
+        network::connection::{PacketHeader, HEADER_LENGTH},
+        types::{tests::device, OpenRGBLed},
+    };
+    use std::{
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    };
+    use tokio::io::{duplex, AsyncReadExt, DuplexStream, ReadBuf};
+
+    /// A request received by a [`FakeServer`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) struct Request {
+        pub device: u32,
+        pub command: u32,
+        pub payload: Vec<u8>,
+    }
+
+    impl Request {
+        /// The colors sent by an led update.
+        pub(crate) fn colors(&self) -> Vec<Color> {
+            let start = match self.command {
+                1050 => 6,
+                1051 => 10,
+                1052 => 4,
+                _ => return Vec::new(),
+            };
+
+            self.payload[start..]
+                .chunks(4)
+                .map(|color| Color::new(color[0], color[1], color[2]))
+                .collect()
+        }
+    }
+
+    /// The client's end of a connection to a [`FakeServer`], which fails like a dropped
+    /// connection once it sent the number of requests it's allowed to.
+    struct ClientEnd {
+        stream: DuplexStream,
+        requests_left: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl AsyncRead for ClientEnd {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.stream).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for ClientEnd {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            if *self.requests_left.lock().unwrap() == Some(0) {
+                return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+            }
+
+            Pin::new(&mut self.stream).poll_write(cx, buf)
+        }
+
+        /// Every request ends with a flush.
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            let result = Pin::new(&mut self.stream).poll_flush(cx);
+            if let Poll::Ready(Ok(())) = result {
+                if let Some(requests_left) = self.requests_left.lock().unwrap().as_mut() {
+                    *requests_left = requests_left.saturating_sub(1);
+                }
+            }
+            result
+        }
+
+        fn poll_shutdown(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.stream).poll_shutdown(cx)
+        }
+    }
+
+    /// An OpenRGB server in memory, answering requests for the devices and keeping every
+    /// request it received.
+    pub(crate) struct FakeServer {
+        requests: Arc<Mutex<Vec<Request>>>,
+        requests_left: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl FakeServer {
+        /// Connects a client to a server with the devices.
+        pub(crate) async fn connect(devices: Vec<OpenRGBDevice>) -> (OpenRGBClient, FakeServer) {
+            let (client, server) = duplex(64 * 1024);
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let requests_left = Arc::new(Mutex::new(None));
+            tokio::spawn(Self::serve(server, devices, requests.clone()));
+
+            let client = ClientEnd {
+                stream: client,
+                requests_left: requests_left.clone(),
+            };
+            let client = OpenRGBClient::with_transport(client, "test").await.unwrap();
+            requests.lock().unwrap().clear();
+
+            let server = FakeServer {
+                requests,
+                requests_left,
+            };
+            (client, server)
+        }
+
+        async fn serve(
+            mut stream: DuplexStream,
+            devices: Vec<OpenRGBDevice>,
+            requests: Arc<Mutex<Vec<Request>>>,
+        ) {
+            loop {
+                let mut header = [0; HEADER_LENGTH];
+                if stream.read_exact(&mut header).await.is_err() {
+                    return;
+                }
+                let header = PacketHeader::parse(&header).unwrap();
+                let mut payload = vec![0; header.length as usize];
+                stream.read_exact(&mut payload).await.unwrap();
+
+                let command = header.command;
+                requests.lock().unwrap().push(Request {
+                    device: header.device,
+                    command,
+                    payload,
+                });
+
+                let response = if command == Command::RequestControllerCount as u32 {
+                    (devices.len() as u32).to_le_bytes().to_vec()
+                } else if command == Command::RequestControllerData as u32 {
+                    let mut response = Vec::new();
+                    let device = &devices[header.device as usize];
+                    device.serialize(&mut response).await.unwrap();
+                    response
+                } else {
+                    continue;
+                };
+
+                let header = PacketHeader::new(header.device, command, response.len() as u32);
+                stream.write_all(&header.to_bytes()).await.unwrap();
+                stream.write_all(&response).await.unwrap();
+            }
+        }
+
+        /// Makes the client fail to send anything after the number of further requests.
+        pub(crate) fn hang_up_after(&self, requests: usize) {
+            *self.requests_left.lock().unwrap() = Some(requests);
+        }
+
+        /// Takes the requests received so far. A round trip is made first so none are still on
+        /// their way.
+        pub(crate) async fn take_requests(&self, client: &mut OpenRGBClient) -> Vec<Request> {
+            client.get_device_count().await.unwrap();
+            let mut requests = std::mem::take(&mut *self.requests.lock().unwrap());
+            requests.pop();
+            requests
+        }
+    }
+
+    #[tokio::test]
+    async fn commit_sends_changed_leds() {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+        client.get_device(0).await.unwrap();
+        server.take_requests(&mut client).await;
+
+        client.staged_colors(0).unwrap()[1] = Color::WHITE;
+        client.commit(0).await.unwrap();
+
+        let requests = server.take_requests(&mut client).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].command, Command::UpdateSingleLed as u32);
+        assert_eq!(requests[0].colors(), vec![Color::WHITE]);
+        assert_eq!(client.colors(0).unwrap()[1], Color::WHITE);
+
+        // Nothing is left to send.
+        client.commit(0).await.unwrap();
+        assert!(server.take_requests(&mut client).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_staged_colors_after_failed_commit() {
+        // Long enough that changing two leds is cheaper than updating all of them.
+        let mut strip = device("Strip", "1");
+        strip.zones[0].leds_count = 20;
+        strip.leds = (0..22)
+            .map(|value| OpenRGBLed {
+                name: format!("Led {}", value),
+                value,
+            })
+            .collect();
+        strip.colors = vec![Color::BLACK; 22];
+
+        let (mut client, server) = FakeServer::connect(vec![strip]).await;
+        client.get_device(0).await.unwrap();
+        server.take_requests(&mut client).await;
+
+        // Two leds changed, only the first update gets through.
+        let staged = client.staged_colors(0).unwrap();
+        staged[0] = Color::WHITE;
+        staged[21] = Color::WHITE;
+        server.hang_up_after(1);
+        assert!(client.commit(0).await.is_err());
+        assert_eq!(client.staged_colors(0).unwrap()[21], Color::WHITE);
+
+        // Only the missing led is sent again.
+        server.hang_up_after(2);
+        client.commit(0).await.unwrap();
+        let requests = server.take_requests(&mut client).await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].command, Command::UpdateSingleLed as u32);
+        assert_eq!(requests[1].payload[..4], 21u32.to_le_bytes());
+    }
+}
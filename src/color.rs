@@ -0,0 +1,337 @@
+use crate::OpenRGBError;
+use std::{fmt::Display, str::FromStr};
+
+/// An RGB color as understood by OpenRGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0x00, 0x00, 0x00);
+    pub const WHITE: Color = Color::new(0xFF, 0xFF, 0xFF);
+    pub const RED: Color = Color::new(0xFF, 0x00, 0x00);
+    pub const GREEN: Color = Color::new(0x00, 0xFF, 0x00);
+    pub const BLUE: Color = Color::new(0x00, 0x00, 0xFF);
+    pub const YELLOW: Color = Color::new(0xFF, 0xFF, 0x00);
+    pub const CYAN: Color = Color::new(0x00, 0xFF, 0xFF);
+    pub const MAGENTA: Color = Color::new(0xFF, 0x00, 0xFF);
+    pub const ORANGE: Color = Color::new(0xFF, 0xA5, 0x00);
+    pub const PURPLE: Color = Color::new(0x80, 0x00, 0x80);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses a color from a hex string such as `#FF8800`, `ff8800` or the short form `#f80`.
+    pub fn from_hex(hex: &str) -> Result<Self, OpenRGBError> {
+        let invalid = || OpenRGBError::InvalidColor(hex.to_string());
+        let digits = hex.trim_start_matches('#');
+
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        match digits.len() {
+            6 => {
+                let value = u32::from_str_radix(digits, 16).map_err(|_| invalid())?;
+                Ok(Self::new(
+                    (value >> 16) as u8,
+                    (value >> 8) as u8,
+                    value as u8,
+                ))
+            }
+            3 => {
+                let value = u16::from_str_radix(digits, 16).map_err(|_| invalid())?;
+                let expand = |nibble: u16| (nibble as u8 & 0xF) * 0x11;
+                Ok(Self::new(
+                    expand(value >> 8),
+                    expand(value >> 4),
+                    expand(value),
+                ))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Formats the color as an uppercase hex string with a leading `#`, e.g. `#FF8800`.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    /// Creates a color from hue (degrees, wrapped to `0..360`), saturation and value (both `0.0..=1.0`).
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = value - chroma;
+
+        Self::from_unit(r + m, g + m, b + m)
+    }
+
+    /// Returns the hue (degrees), saturation and value of this color.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (r, g, b) = self.to_unit();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let saturation = if max > 0.0 { delta / max } else { 0.0 };
+        (hue_of(r, g, b, max, delta), saturation, max)
+    }
+
+    /// Creates a color from hue (degrees, wrapped to `0..360`), saturation and lightness (both `0.0..=1.0`).
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = lightness - chroma / 2.0;
+
+        Self::from_unit(r + m, g + m, b + m)
+    }
+
+    /// Linearly interpolates between `self` (`t = 0.0`) and `other` (`t = 1.0`).
+    pub fn lerp(&self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+
+    /// Multiplies every channel by `factor`, saturating at `255`.
+    pub fn scale(&self, factor: f32) -> Self {
+        let factor = factor.max(0.0);
+        let scale = |c: u8| (c as f32 * factor).round().min(255.0) as u8;
+
+        Self::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+
+    fn from_unit(r: f32, g: f32, b: f32) -> Self {
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(to_u8(r), to_u8(g), to_u8(b))
+    }
+
+    fn to_unit(self) -> (f32, f32, f32) {
+        (
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+        )
+    }
+}
+
+/// Maps a hue and chroma to the unshifted rgb components shared by the HSV and HSL conversions.
+fn hue_to_rgb(hue: f32, chroma: f32) -> (f32, f32, f32) {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+    match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    }
+}
+
+fn hue_of(r: f32, g: f32, b: f32, max: f32, delta: f32) -> f32 {
+    if delta == 0.0 {
+        return 0.0;
+    }
+
+    let hue = if (max - r).abs() < f32::EPSILON {
+        60.0 * ((g - b) / delta)
+    } else if (max - g).abs() < f32::EPSILON {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    hue.rem_euclid(360.0)
+}
+
+impl FromStr for Color {
+    type Err = OpenRGBError;
+
+    /// Parses either a hex color or one of the named colors, e.g. `"red"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let named = match s.to_ascii_lowercase().as_str() {
+            "black" => Some(Self::BLACK),
+            "white" => Some(Self::WHITE),
+            "red" => Some(Self::RED),
+            "green" => Some(Self::GREEN),
+            "blue" => Some(Self::BLUE),
+            "yellow" => Some(Self::YELLOW),
+            "cyan" => Some(Self::CYAN),
+            "magenta" => Some(Self::MAGENTA),
+            "orange" => Some(Self::ORANGE),
+            "purple" => Some(Self::PURPLE),
+            _ => None,
+        };
+
+        match named {
+            Some(color) => Ok(color),
+            None => Self::from_hex(s),
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::new(r, g, b)
+    }
+}
+
+impl From<Color> for (u8, u8, u8) {
+    fn from(color: Color) -> Self {
+        (color.r, color.g, color.b)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(
+            Color::from_hex("#FF8800").unwrap(),
+            Color::new(0xFF, 0x88, 0x00)
+        );
+        assert_eq!(
+            Color::from_hex("ff8800").unwrap(),
+            Color::new(0xFF, 0x88, 0x00)
+        );
+        assert_eq!(
+            Color::from_hex("#f80").unwrap(),
+            Color::new(0xFF, 0x88, 0x00)
+        );
+        assert_eq!(Color::from_hex("#000").unwrap(), Color::BLACK);
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        for hex in &[
+            "", "#", "#ff", "#ff88", "#ff88000", "#gg8800", "#+f8800", "#ff 800", "#ff8ü",
+        ] {
+            match Color::from_hex(hex) {
+                Err(OpenRGBError::InvalidColor(input)) => assert_eq!(input, *hex),
+                result => panic!("{:?} parsed as {:?}", hex, result),
+            }
+        }
+    }
+
+    #[test]
+    fn formats_hex() {
+        assert_eq!(Color::new(0xFF, 0x88, 0x00).to_hex(), "#FF8800");
+        assert_eq!(Color::new(0x01, 0x0A, 0xB0).to_string(), "#010AB0");
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("Red".parse::<Color>().unwrap(), Color::RED);
+        assert_eq!("#00f".parse::<Color>().unwrap(), Color::BLUE);
+        assert!("reddish".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn converts_hsv() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::RED);
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::GREEN);
+        assert_eq!(Color::from_hsv(-120.0, 1.0, 1.0), Color::BLUE);
+        assert_eq!(Color::from_hsv(480.0, 1.0, 1.0), Color::GREEN);
+        assert_eq!(Color::from_hsv(200.0, 0.0, 1.0), Color::WHITE);
+        assert_eq!(Color::RED.to_hsv(), (0.0, 1.0, 1.0));
+        assert_eq!(Color::BLUE.to_hsv(), (240.0, 1.0, 1.0));
+        assert_eq!(Color::BLACK.to_hsv(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn converts_hsl() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::RED);
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5), Color::BLUE);
+        assert_eq!(Color::from_hsl(0.0, 1.0, 1.0), Color::WHITE);
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.0), Color::BLACK);
+        assert_eq!(Color::from_hsl(0.0, 0.0, 0.5), Color::new(0x80, 0x80, 0x80));
+    }
+
+    #[test]
+    fn round_trips_hsv() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let color = Color::new(r as u8, g as u8, b as u8);
+                    let (hue, saturation, value) = color.to_hsv();
+                    assert_eq!(Color::from_hsv(hue, saturation, value), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_hsl() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let color = Color::new(r as u8, g as u8, b as u8);
+                    let (hue, saturation, value) = color.to_hsv();
+
+                    // Converts HSV to HSL to go back through `from_hsl`.
+                    let lightness = value * (1.0 - saturation / 2.0);
+                    let saturation = match lightness {
+                        l if l <= 0.0 || l >= 1.0 => 0.0,
+                        l => (value - l) / l.min(1.0 - l),
+                    };
+
+                    assert_eq!(Color::from_hsl(hue, saturation, lightness), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blends() {
+        assert_eq!(
+            Color::BLACK.lerp(Color::WHITE, 0.5),
+            Color::new(0x80, 0x80, 0x80)
+        );
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 2.0), Color::WHITE);
+        assert_eq!(
+            Color::new(100, 200, 50).scale(2.0),
+            Color::new(200, 255, 100)
+        );
+        assert_eq!(Color::WHITE.scale(-1.0), Color::BLACK);
+    }
+}
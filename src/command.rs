@@ -2,10 +2,16 @@ use crate::OpenRGBError;
 use std::{convert::TryFrom, fmt::Display};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     SetClientName = 50,
     RequestControllerCount = 0,
     RequestControllerData = 1,
+    DeviceListUpdated = 100,
+    RequestProfileList = 150,
+    RequestSaveProfile = 151,
+    RequestLoadProfile = 152,
+    RequestDeleteProfile = 153,
     ResizeZone = 1000,
     UpdateLeds = 1050,
     UpdateZoneLeds = 1051,
@@ -20,6 +26,11 @@ impl Display for Command {
             Self::SetClientName => "SetClientName",
             Self::RequestControllerCount => "RequestControllerCount",
             Self::RequestControllerData => "RequestControllerData",
+            Self::DeviceListUpdated => "DeviceListUpdated",
+            Self::RequestProfileList => "RequestProfileList",
+            Self::RequestSaveProfile => "RequestSaveProfile",
+            Self::RequestLoadProfile => "RequestLoadProfile",
+            Self::RequestDeleteProfile => "RequestDeleteProfile",
             Self::ResizeZone => "ResizeZone",
             Self::UpdateLeds => "UpdateLeds",
             Self::UpdateZoneLeds => "UpdateZoneLeds",
@@ -40,6 +51,11 @@ impl TryFrom<u32> for Command {
             50 => Command::SetClientName,
             0 => Command::RequestControllerCount,
             1 => Command::RequestControllerData,
+            100 => Command::DeviceListUpdated,
+            150 => Command::RequestProfileList,
+            151 => Command::RequestSaveProfile,
+            152 => Command::RequestLoadProfile,
+            153 => Command::RequestDeleteProfile,
             1000 => Command::ResizeZone,
             1050 => Command::UpdateLeds,
             1051 => Command::UpdateZoneLeds,
@@ -0,0 +1,180 @@
+use crate::{Color, OpenRGBZone};
+
+// Wire sizes of each update packet including the 16 byte header.
+const SINGLE_LED_COST: usize = 16 + 4 + 4;
+const ZONE_LEDS_BASE_COST: usize = 16 + 4 + 4 + 2;
+const LEDS_BASE_COST: usize = 16 + 4 + 2;
+const COLOR_COST: usize = 4;
+
+/// A single packet worth of led changes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LedUpdate {
+    Single { led_id: usize, color: Color },
+    Zone { zone_id: usize, colors: Vec<Color> },
+    All { colors: Vec<Color> },
+}
+
+/// Plans the set of update packets with the smallest wire size that turns `old` into `new`.
+pub(crate) fn plan_updates(zones: &[OpenRGBZone], old: &[Color], new: &[Color]) -> Vec<LedUpdate> {
+    let changed: Vec<usize> = (0..new.len())
+        .filter(|&id| old.get(id) != Some(&new[id]))
+        .collect();
+
+    if changed.is_empty() {
+        return vec![];
+    }
+
+    let full_update = || {
+        vec![LedUpdate::All {
+            colors: new.to_vec(),
+        }]
+    };
+
+    let zone_total: usize = zones.iter().map(|zone| zone.leds_count as usize).sum();
+    if zone_total != new.len() || old.len() != new.len() {
+        return full_update();
+    }
+
+    let mut updates = Vec::new();
+    let mut cost = 0;
+    let mut offset = 0;
+
+    for (zone_id, zone) in zones.iter().enumerate() {
+        let range = offset..offset + zone.leds_count as usize;
+        offset = range.end;
+
+        let zone_changes: Vec<usize> = changed
+            .iter()
+            .copied()
+            .filter(|id| range.contains(id))
+            .collect();
+
+        if zone_changes.is_empty() {
+            continue;
+        }
+
+        let singles_cost = zone_changes.len() * SINGLE_LED_COST;
+        let zone_cost = ZONE_LEDS_BASE_COST + range.len() * COLOR_COST;
+
+        if singles_cost <= zone_cost {
+            cost += singles_cost;
+            updates.extend(zone_changes.into_iter().map(|led_id| LedUpdate::Single {
+                led_id,
+                color: new[led_id],
+            }));
+        } else {
+            cost += zone_cost;
+            updates.push(LedUpdate::Zone {
+                zone_id,
+                colors: new[range].to_vec(),
+            });
+        }
+    }
+
+    if cost > LEDS_BASE_COST + new.len() * COLOR_COST {
+        return full_update();
+    }
+
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zones(sizes: &[u32]) -> Vec<OpenRGBZone> {
+        sizes
+            .iter()
+            .map(|&size| OpenRGBZone {
+                name: String::new(),
+                zone_type: 1,
+                leds_count: size,
+                leds_min: size,
+                leds_max: size,
+                matrix_map: None,
+            })
+            .collect()
+    }
+
+    fn changed(old: &[Color], leds: &[usize]) -> Vec<Color> {
+        let mut new = old.to_vec();
+        for &led in leds {
+            new[led] = Color::RED;
+        }
+        new
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let old = vec![Color::BLACK; 20];
+        assert_eq!(plan_updates(&zones(&[10, 10]), &old, &old), vec![]);
+    }
+
+    #[test]
+    fn few_changes_use_single_leds() {
+        let old = vec![Color::BLACK; 20];
+        let new = changed(&old, &[3, 12]);
+
+        assert_eq!(
+            plan_updates(&zones(&[10, 10]), &old, &new),
+            vec![
+                LedUpdate::Single {
+                    led_id: 3,
+                    color: Color::RED
+                },
+                LedUpdate::Single {
+                    led_id: 12,
+                    color: Color::RED
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn many_changes_in_a_zone_use_the_zone() {
+        let old = vec![Color::BLACK; 20];
+        let new = changed(&old, &[1, 12, 13, 14]);
+
+        assert_eq!(
+            plan_updates(&zones(&[10, 10]), &old, &new),
+            vec![
+                LedUpdate::Single {
+                    led_id: 1,
+                    color: Color::RED
+                },
+                LedUpdate::Zone {
+                    zone_id: 1,
+                    colors: new[10..].to_vec()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_across_zones_use_the_whole_device() {
+        let old = vec![Color::BLACK; 20];
+        let new = changed(&old, &[0, 1, 2, 10, 11, 12]);
+
+        assert_eq!(
+            plan_updates(&zones(&[10, 10]), &old, &new),
+            vec![LedUpdate::All { colors: new }]
+        );
+    }
+
+    #[test]
+    fn mismatched_sizes_use_the_whole_device() {
+        let old = vec![Color::BLACK; 20];
+        let new = changed(&old, &[0]);
+        assert_eq!(
+            plan_updates(&zones(&[10, 5]), &old, &new),
+            vec![LedUpdate::All {
+                colors: new.clone()
+            }]
+        );
+
+        assert_eq!(
+            plan_updates(&zones(&[10, 10]), &old[..19], &new),
+            vec![LedUpdate::All { colors: new }]
+        );
+    }
+}
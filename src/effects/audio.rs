@@ -0,0 +1,135 @@
+use super::{Effect, LedLayout};
+use crate::{AudioFrame, Color};
+use std::time::Duration;
+
+/// An effect reacting to audio, see [`crate::OpenRGBClient::play_audio`].
+pub trait AudioEffect: Effect {
+    /// Called with the latest analysis before every frame is rendered.
+    fn update(&mut self, audio: &AudioFrame);
+}
+
+impl<E: AudioEffect + ?Sized> AudioEffect for Box<E> {
+    fn update(&mut self, audio: &AudioFrame) {
+        (**self).update(audio)
+    }
+}
+
+/// Shows the level of every band as a bar, with the lowest band on the left.
+///
+/// On matrix zones bars grow from the bottom, on linear zones every led shows the level of its
+/// band through its brightness. Bars fade from `low` to `high` as they get taller.
+#[derive(Debug, Clone)]
+pub struct SpectrumBars {
+    pub low: Color,
+    pub high: Color,
+    pub background: Color,
+    bands: Vec<f32>,
+    layout: LedLayout,
+}
+
+impl SpectrumBars {
+    pub fn new(low: Color, high: Color) -> Self {
+        Self {
+            low,
+            high,
+            background: Color::BLACK,
+            bands: Vec::new(),
+            layout: LedLayout::default(),
+        }
+    }
+
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    fn is_linear(&self) -> bool {
+        let mut rows = self.layout.positions.iter().map(|(_, y)| *y);
+        let first = rows.next();
+        rows.all(|y| Some(y) == first)
+    }
+}
+
+impl Effect for SpectrumBars {
+    fn configure(&mut self, layout: &LedLayout) {
+        self.layout = layout.clone();
+    }
+
+    fn render(&mut self, _t: Duration, frame: &mut [Color]) {
+        if self.bands.is_empty() {
+            frame.iter_mut().for_each(|led| *led = self.background);
+            return;
+        }
+
+        let linear = self.is_linear();
+        let bands = self.bands.len();
+
+        for (led, (x, y)) in frame.iter_mut().zip(&self.layout.positions) {
+            let level = self.bands[((x * bands as f32) as usize).min(bands - 1)];
+
+            *led = if linear {
+                self.low.lerp(self.high, level).scale(level)
+            } else {
+                let height = 1.0 - y;
+
+                if level > 0.0 && height <= level {
+                    self.low.lerp(self.high, height)
+                } else {
+                    self.background
+                }
+            };
+        }
+    }
+}
+
+impl AudioEffect for SpectrumBars {
+    fn update(&mut self, audio: &AudioFrame) {
+        self.bands.clone_from(&audio.bands);
+    }
+}
+
+/// Flashes a color on every beat, fading out until the next one.
+#[derive(Debug, Clone)]
+pub struct BeatPulse {
+    pub color: Color,
+    /// Time it takes a flash to fade out completely.
+    pub decay: Duration,
+    beat: bool,
+    last_beat: Option<Duration>,
+}
+
+impl BeatPulse {
+    pub fn new(color: Color, decay: Duration) -> Self {
+        Self {
+            color,
+            decay,
+            beat: false,
+            last_beat: None,
+        }
+    }
+}
+
+impl Effect for BeatPulse {
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        if std::mem::take(&mut self.beat) {
+            self.last_beat = Some(t);
+        }
+
+        let brightness = match self.last_beat {
+            Some(last_beat) => {
+                let since = t.checked_sub(last_beat).unwrap_or_default();
+                1.0 - since.as_secs_f32() / self.decay.as_secs_f32().max(f32::EPSILON)
+            }
+            None => 0.0,
+        };
+        let color = self.color.scale(brightness.max(0.0));
+
+        frame.iter_mut().for_each(|led| *led = color);
+    }
+}
+
+impl AudioEffect for BeatPulse {
+    fn update(&mut self, audio: &AudioFrame) {
+        self.beat |= audio.beat;
+    }
+}
@@ -0,0 +1,49 @@
+use super::Effect;
+use crate::Color;
+use std::{f32::consts::PI, time::Duration};
+
+/// Fades a color in and out.
+#[derive(Debug, Clone)]
+pub struct Breathing {
+    pub color: Color,
+    /// Time it takes to fade in and out once.
+    pub period: Duration,
+}
+
+impl Breathing {
+    pub fn new(color: Color, period: Duration) -> Self {
+        Self { color, period }
+    }
+}
+
+impl Effect for Breathing {
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        let phase = t.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON);
+        let brightness = (1.0 - (2.0 * PI * phase).cos()) / 2.0;
+        let color = self.color.scale(brightness);
+
+        frame.iter_mut().for_each(|led| *led = color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_in_and_out_over_the_period() {
+        let mut breathing = Breathing::new(Color::new(200, 100, 0), Duration::from_secs(2));
+        let mut frame = vec![Color::WHITE; 3];
+
+        for (millis, expected) in &[
+            (0, Color::BLACK),
+            (500, Color::new(100, 50, 0)),
+            (1000, Color::new(200, 100, 0)),
+            (1500, Color::new(100, 50, 0)),
+            (2000, Color::BLACK),
+        ] {
+            breathing.render(Duration::from_millis(*millis), &mut frame);
+            assert_eq!(frame, vec![*expected; 3], "at {} ms", millis);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use super::{Effect, FrameClock, LedLayout};
+use crate::{Color, OpenRGBClient, OpenRGBError, OpenRGBResult, OpenRGBZone};
+use std::{collections::BTreeMap, time::Duration};
+
+/// The shape of a zone's leds in its own coordinates, before it is placed on the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneShape {
+    /// Leds evenly spread along the line through the points, for strips and other linear zones.
+    Polyline(Vec<(f32, f32)>),
+    /// Leds on the cells of the zone's matrix map, each cell being one unit wide.
+    Matrix,
+}
+
+/// Where a zone is on the canvas.
+///
+/// The zone's shape is scaled, then rotated around its origin and finally moved to `position`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub device_id: usize,
+    pub zone_id: usize,
+    pub shape: ZoneShape,
+    pub position: (f32, f32),
+    /// Rotation in degrees, clockwise as the y axis points down.
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl Placement {
+    pub fn new(device_id: usize, zone_id: usize, shape: ZoneShape) -> Self {
+        Self {
+            device_id,
+            zone_id,
+            shape,
+            position: (0.0, 0.0),
+            rotation: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.position = (x, y);
+        self
+    }
+
+    pub fn with_rotation(mut self, degrees: f32) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Gets the position of every led of the zone on the canvas in led order, leds without a
+    /// position (such as unmapped matrix leds) are `None`.
+    pub fn led_positions(&self, zone: &OpenRGBZone) -> OpenRGBResult<Vec<Option<(f32, f32)>>> {
+        let count = zone.leds_count as usize;
+
+        let local = match &self.shape {
+            ZoneShape::Polyline(points) => polyline_positions(points, count),
+            ZoneShape::Matrix => {
+                let map = zone
+                    .matrix_map
+                    .as_ref()
+                    .ok_or(OpenRGBError::NotAMatrix(self.zone_id))?;
+
+                let mut positions = vec![None; count];
+                for (x, y, led_id) in map.leds() {
+                    if let Some(position) = positions.get_mut(led_id) {
+                        *position = Some((x as f32 + 0.5, y as f32 + 0.5));
+                    }
+                }
+
+                positions
+            }
+        };
+
+        Ok(local
+            .into_iter()
+            .map(|point| point.map(|point| self.transform(point)))
+            .collect())
+    }
+
+    fn transform(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (x, y) = (x * self.scale, y * self.scale);
+
+        (
+            x * cos - y * sin + self.position.0,
+            x * sin + y * cos + self.position.1,
+        )
+    }
+}
+
+fn polyline_positions(points: &[(f32, f32)], count: usize) -> Vec<Option<(f32, f32)>> {
+    let lengths: Vec<f32> = points
+        .windows(2)
+        .map(|pair| ((pair[1].0 - pair[0].0).powi(2) + (pair[1].1 - pair[0].1).powi(2)).sqrt())
+        .collect();
+    let length: f32 = lengths.iter().sum();
+
+    if length == 0.0 {
+        return vec![points.first().copied(); count];
+    }
+
+    (0..count)
+        .map(|led| {
+            // Leds sit in the middle of equally long pieces of the line.
+            let mut distance = (led as f32 + 0.5) / count as f32 * length;
+
+            for (pair, segment_length) in points.windows(2).zip(&lengths) {
+                if distance <= *segment_length && *segment_length > 0.0 {
+                    let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                    let t = distance / segment_length;
+                    return Some((x1 + (x2 - x1) * t, y1 + (y2 - y1) * t));
+                }
+
+                distance -= segment_length;
+            }
+
+            points.last().copied()
+        })
+        .collect()
+}
+
+/// A 2D space containing zones of several devices, so effects can travel across all of them.
+///
+/// Any [`Effect`] can be rendered on the canvas, the positions it is configured with are those
+/// of the leds on the canvas, scaled so the larger side of the canvas spans `0.0..=1.0`.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::{effects::*, *};
+/// # use std::time::Duration;
+/// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+/// client.refresh_all().await?;
+///
+/// let mut canvas = Canvas::new();
+/// canvas.place(Placement::new(0, 0, ZoneShape::Matrix).with_position(0.0, 0.0));
+/// canvas.place(
+///     Placement::new(1, 0, ZoneShape::Polyline(vec![(0.0, 0.0), (0.0, 10.0)]))
+///         .with_position(30.0, 0.0),
+/// );
+///
+/// let mut wave = Wave::new(Color::CYAN, Color::BLACK);
+/// canvas
+///     .run(&mut client, &mut wave, 30.0, Some(Duration::from_secs(10)))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Canvas {
+    placements: Vec<Placement>,
+}
+
+/// The leds of a canvas, see [`Canvas::layout`].
+#[derive(Debug, Clone, Default)]
+pub struct CanvasLayout {
+    /// Normalized positions of the leds, as passed to effects.
+    pub layout: LedLayout,
+    /// Positions of the leds in canvas coordinates.
+    pub points: Vec<(f32, f32)>,
+    /// The device and device led index of every led.
+    pub leds: Vec<(usize, usize)>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places a zone on the canvas, replacing any earlier placement of the zone.
+    pub fn place(&mut self, placement: Placement) {
+        self.placements.retain(|placed| {
+            (placed.device_id, placed.zone_id) != (placement.device_id, placement.zone_id)
+        });
+        self.placements.push(placement);
+    }
+
+    pub fn remove(&mut self, device_id: usize, zone_id: usize) -> Option<Placement> {
+        let index = self
+            .placements
+            .iter()
+            .position(|placed| (placed.device_id, placed.zone_id) == (device_id, zone_id))?;
+
+        Some(self.placements.remove(index))
+    }
+
+    pub fn placements(&self) -> &[Placement] {
+        &self.placements
+    }
+
+    /// Computes the position of every placed led using the client's cached devices.
+    pub fn layout(&self, client: &OpenRGBClient) -> OpenRGBResult<CanvasLayout> {
+        let mut canvas = CanvasLayout::default();
+
+        for placement in &self.placements {
+            let device = client
+                .device(placement.device_id)
+                .ok_or(OpenRGBError::InvalidId(placement.device_id))?;
+            let zone = device
+                .zones
+                .get(placement.zone_id)
+                .ok_or(OpenRGBError::InvalidId(placement.zone_id))?;
+            let offset = device.zone_led_offset(placement.zone_id).unwrap_or(0);
+
+            for (led_id, point) in placement.led_positions(zone)?.into_iter().enumerate() {
+                if let Some(point) = point {
+                    canvas.points.push(point);
+                    canvas.leds.push((placement.device_id, offset + led_id));
+                }
+            }
+        }
+
+        let min_x = canvas
+            .points
+            .iter()
+            .map(|(x, _)| *x)
+            .fold(f32::INFINITY, f32::min);
+        let min_y = canvas
+            .points
+            .iter()
+            .map(|(_, y)| *y)
+            .fold(f32::INFINITY, f32::min);
+        let max_x = canvas
+            .points
+            .iter()
+            .map(|(x, _)| *x)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let max_y = canvas
+            .points
+            .iter()
+            .map(|(_, y)| *y)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let extent = (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+
+        canvas.layout.positions = canvas
+            .points
+            .iter()
+            .map(|(x, y)| ((x - min_x) / extent, (y - min_y) / extent))
+            .collect();
+
+        Ok(canvas)
+    }
+
+    /// Maps a frame rendered for the canvas layout back to the colors of every placed device.
+    ///
+    /// Leds that aren't on the canvas keep the colors the client last sent them.
+    pub fn sample(
+        &self,
+        client: &OpenRGBClient,
+        layout: &CanvasLayout,
+        frame: &[Color],
+    ) -> BTreeMap<usize, Vec<Color>> {
+        let mut devices: BTreeMap<usize, Vec<Color>> = BTreeMap::new();
+
+        for ((device_id, led), color) in layout.leds.iter().zip(frame) {
+            let colors = devices
+                .entry(*device_id)
+                .or_insert_with(|| client.colors(*device_id).unwrap_or(&[]).to_vec());
+
+            if let Some(led) = colors.get_mut(*led) {
+                *led = *color;
+            }
+        }
+
+        devices
+    }
+
+    /// Renders the effect on the canvas at a fixed frame rate for `duration`, or forever if
+    /// `None`, sending the colors of every placed device each frame.
+    pub async fn run<E: Effect + ?Sized>(
+        &self,
+        client: &mut OpenRGBClient,
+        effect: &mut E,
+        fps: f32,
+        duration: Option<Duration>,
+    ) -> OpenRGBResult<()> {
+        let layout = self.layout(client)?;
+        effect.configure(&layout.layout);
+
+        let mut frame = vec![Color::BLACK; layout.leds.len()];
+        let mut clock = FrameClock::new(fps, duration);
+
+        while let Some(t) = clock.next_frame().await {
+            effect.render(t, &mut frame);
+
+            for (device_id, colors) in self.sample(client, &layout, &frame) {
+                client.update_leds(device_id, &colors).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::FakeServer, types::tests::device};
+
+    fn assert_close(actual: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (actual, expected) in actual.iter().zip(expected) {
+            let distance = (actual.0 - expected.0).abs() + (actual.1 - expected.1).abs();
+            assert!(distance < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn places_leds_along_polylines() {
+        let zone = &device("Strip", "1").zones[0];
+        let corner = ZoneShape::Polyline(vec![(0.0, 0.0), (2.0, 0.0), (2.0, 1.0)]);
+
+        let positions: Vec<_> = Placement::new(0, 0, corner)
+            .led_positions(zone)
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        assert_close(&positions, &[(0.5, 0.0), (1.5, 0.0), (2.0, 0.5)]);
+    }
+
+    #[test]
+    fn transforms_matrix_cells() {
+        let zone = &device("Strip", "1").zones[1];
+        let placement = Placement::new(0, 1, ZoneShape::Matrix)
+            .with_scale(2.0)
+            .with_rotation(90.0)
+            .with_position(10.0, 0.0);
+
+        let positions: Vec<_> = placement
+            .led_positions(zone)
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        assert_close(&positions, &[(9.0, 1.0), (9.0, 5.0)]);
+
+        let strip = &device("Strip", "1").zones[0];
+        assert!(matches!(
+            Placement::new(0, 0, ZoneShape::Matrix).led_positions(strip),
+            Err(OpenRGBError::NotAMatrix(0))
+        ));
+    }
+
+    #[tokio::test]
+    async fn samples_frames_onto_placed_zones() {
+        let devices = vec![device("Strip", "1"), device("Fan", "2")];
+        let (mut client, _server) = FakeServer::connect(devices).await;
+        client.refresh_all().await.unwrap();
+
+        let mut canvas = Canvas::new();
+        canvas.place(Placement::new(
+            0,
+            0,
+            ZoneShape::Polyline(vec![(0.0, 0.0), (3.0, 0.0)]),
+        ));
+        canvas.place(Placement::new(1, 1, ZoneShape::Matrix).with_position(0.0, 3.0));
+        // Placing a zone again moves it.
+        canvas.place(Placement::new(1, 1, ZoneShape::Matrix).with_position(0.0, 3.0));
+        assert_eq!(canvas.placements().len(), 2);
+
+        let layout = canvas.layout(&client).unwrap();
+        assert_eq!(layout.leds, vec![(0, 0), (0, 1), (0, 2), (1, 3), (1, 4)]);
+        assert_close(
+            &layout.points,
+            &[(0.5, 0.0), (1.5, 0.0), (2.5, 0.0), (0.5, 3.5), (2.5, 3.5)],
+        );
+        // The canvas is taller than it is wide, so its height spans 0 to 1.
+        assert_close(
+            &layout.layout.positions,
+            &[
+                (0.0, 0.0),
+                (1.0 / 3.5, 0.0),
+                (2.0 / 3.5, 0.0),
+                (0.0, 1.0),
+                (2.0 / 3.5, 1.0),
+            ],
+        );
+
+        let frame = [Color::CYAN; 5];
+        let sampled = canvas.sample(&client, &layout, &frame);
+        let (strip, fan) = (&sampled[&0], &sampled[&1]);
+        assert_eq!(strip[..3], [Color::CYAN; 3]);
+        assert_eq!(strip[3..], [Color::WHITE, Color::BLACK]);
+        assert_eq!(fan[..3], [Color::RED, Color::GREEN, Color::BLUE]);
+        assert_eq!(fan[3..], [Color::CYAN; 2]);
+
+        assert!(canvas.remove(0, 0).is_some());
+        assert!(canvas.remove(0, 0).is_none());
+        assert_eq!(canvas.layout(&client).unwrap().leds, vec![(1, 3), (1, 4)]);
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Effect, LedLayout};
+use crate::Color;
+use std::time::Duration;
+
+/// How a layer is combined with the layers below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The layer replaces what is below it.
+    #[default]
+    Normal,
+    /// Channels are added together, saturating at full brightness.
+    Add,
+    /// Channels are multiplied, darkening what is below.
+    Multiply,
+    /// The inverse of multiplying the inverted channels, brightening what is below.
+    Screen,
+}
+
+impl BlendMode {
+    pub fn blend(&self, base: Color, layer: Color) -> Color {
+        let channel = |base: u8, layer: u8| -> u8 {
+            let (base, layer) = (base as u16, layer as u16);
+
+            match self {
+                Self::Normal => layer as u8,
+                Self::Add => (base + layer).min(255) as u8,
+                Self::Multiply => (base * layer / 255) as u8,
+                Self::Screen => (255 - (255 - base) * (255 - layer) / 255) as u8,
+            }
+        };
+
+        Color::new(
+            channel(base.r, layer.r),
+            channel(base.g, layer.g),
+            channel(base.b, layer.b),
+        )
+    }
+}
+
+/// An effect in a [`Compositor`].
+pub struct Layer {
+    pub effect: Box<dyn Effect>,
+    /// How much of the blended result is shown, from `0.0` (invisible) to `1.0`.
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// The leds the layer is drawn on, all leds if `None`.
+    pub mask: Option<Vec<bool>>,
+}
+
+impl Layer {
+    pub fn new<E: Effect + 'static>(effect: E) -> Self {
+        Self {
+            effect: Box::new(effect),
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
+            mask: None,
+        }
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_mask(mut self, mask: Vec<bool>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Only draws the layer on the given leds of a target with `len` leds.
+    pub fn with_mask_leds(self, leds: &[usize], len: usize) -> Self {
+        let mut mask = vec![false; len];
+        for led in leds.iter().filter(|led| **led < len) {
+            mask[*led] = true;
+        }
+
+        self.with_mask(mask)
+    }
+
+    fn draws(&self, led: usize) -> bool {
+        match &self.mask {
+            Some(mask) => mask.get(led).copied().unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// Stacks effects on top of each other, rendering them bottom to top into a single frame.
+///
+/// The compositor is an effect itself, so it can be driven by an [`super::EffectRunner`] to send
+/// one combined frame per tick.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::{effects::*, *};
+/// # use std::time::Duration;
+/// let mut compositor = Compositor::new();
+/// compositor.push(Layer::new(Rainbow::default()));
+/// compositor.push(
+///     Layer::new(Breathing::new(Color::WHITE, Duration::from_secs(1)))
+///         .with_blend_mode(BlendMode::Screen)
+///         .with_opacity(0.5),
+/// );
+///
+/// let runner = EffectRunner::new(compositor, FrameTarget::Device(0), 30.0);
+/// ```
+#[derive(Default)]
+pub struct Compositor {
+    layers: Vec<Layer>,
+    layout: LedLayout,
+    scratch: Vec<Color>,
+}
+
+impl Compositor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a layer on top of the others, returning its index.
+    pub fn push(&mut self, mut layer: Layer) -> usize {
+        layer.effect.configure(&self.layout);
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    /// Inserts a layer at the index, shifting the layers above it up.
+    pub fn insert(&mut self, index: usize, mut layer: Layer) {
+        layer.effect.configure(&self.layout);
+        self.layers.insert(index, layer);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Layer> {
+        if index < self.layers.len() {
+            Some(self.layers.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn layer_mut(&mut self, index: usize) -> Option<&mut Layer> {
+        self.layers.get_mut(index)
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [Layer] {
+        &mut self.layers
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+impl Effect for Compositor {
+    fn configure(&mut self, layout: &LedLayout) {
+        self.layout = layout.clone();
+
+        for layer in &mut self.layers {
+            layer.effect.configure(layout);
+        }
+    }
+
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        frame.iter_mut().for_each(|led| *led = Color::BLACK);
+        self.scratch.resize(frame.len(), Color::BLACK);
+
+        for layer in &mut self.layers {
+            let opacity = layer.opacity.clamp(0.0, 1.0);
+            if opacity == 0.0 {
+                continue;
+            }
+
+            // Layers that only draw some leds leave the rest black rather than showing what the
+            // layer below drew.
+            self.scratch.iter_mut().for_each(|led| *led = Color::BLACK);
+            layer.effect.render(t, &mut self.scratch);
+
+            for (led, (base, color)) in frame.iter_mut().zip(&self.scratch).enumerate() {
+                if layer.draws(led) {
+                    *base = base.lerp(layer.blend_mode.blend(*base, *color), opacity);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::Static;
+
+    /// Only draws the first led.
+    struct Dot(Color);
+
+    impl Effect for Dot {
+        fn render(&mut self, _t: Duration, frame: &mut [Color]) {
+            frame[0] = self.0;
+        }
+    }
+
+    #[test]
+    fn sparse_layers_blend_black_elsewhere() {
+        let base = Color::new(100, 100, 100);
+        let dot = Color::new(200, 50, 0);
+        let modes = [
+            BlendMode::Normal,
+            BlendMode::Add,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+        ];
+
+        for blend_mode in &modes {
+            for opacity in &[1.0, 0.5] {
+                let mut compositor = Compositor::new();
+                compositor.push(Layer::new(Static::new(base)));
+                compositor.push(
+                    Layer::new(Dot(dot))
+                        .with_blend_mode(*blend_mode)
+                        .with_opacity(*opacity),
+                );
+
+                let expected = |layer: Color| base.lerp(blend_mode.blend(base, layer), *opacity);
+                for _ in 0..2 {
+                    let mut frame = vec![Color::WHITE; 3];
+                    compositor.render(Duration::from_secs(0), &mut frame);
+
+                    let context = format!("{:?} at {}", blend_mode, opacity);
+                    assert_eq!(frame[0], expected(dot), "{}", context);
+                    assert_eq!(frame[1..], [expected(Color::BLACK); 2], "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn masks_and_hidden_layers() {
+        let mut compositor = Compositor::new();
+        compositor.push(Layer::new(Static::new(Color::RED)).with_mask_leds(&[1], 3));
+        compositor.push(Layer::new(Static::new(Color::BLUE)).with_opacity(0.0));
+
+        let mut frame = vec![Color::WHITE; 3];
+        compositor.render(Duration::from_secs(0), &mut frame);
+        assert_eq!(frame, vec![Color::BLACK, Color::RED, Color::BLACK]);
+    }
+}
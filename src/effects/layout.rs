@@ -0,0 +1,85 @@
+use crate::{OpenRGBDevice, OpenRGBZone};
+
+/// The position of every led of an effect's target, normalized to `0.0..=1.0` on both axes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LedLayout {
+    pub positions: Vec<(f32, f32)>,
+}
+
+impl LedLayout {
+    /// Leds evenly spread along a horizontal line through the middle.
+    pub fn linear(count: usize) -> Self {
+        Self {
+            positions: (0..count)
+                .map(|led| (Self::spread(led, count), 0.5))
+                .collect(),
+        }
+    }
+
+    /// Uses the matrix map of matrix zones and lays out other zones linearly.
+    pub fn for_zone(zone: &OpenRGBZone) -> Self {
+        let count = zone.leds_count as usize;
+
+        let map = match &zone.matrix_map {
+            Some(map) => map,
+            None => return Self::linear(count),
+        };
+
+        let mut layout = Self::linear(count);
+        for (x, y, led_id) in map.leds() {
+            if let Some(position) = layout.positions.get_mut(led_id) {
+                *position = (
+                    Self::spread(x as usize, map.width as usize),
+                    Self::spread(y as usize, map.height as usize),
+                );
+            }
+        }
+
+        layout
+    }
+
+    /// Stacks the layouts of the device's zones from top to bottom, each in its own band.
+    pub fn for_device(device: &OpenRGBDevice) -> Self {
+        let bands = device.zones.len().max(1) as f32;
+
+        let mut positions: Vec<(f32, f32)> = device
+            .zones
+            .iter()
+            .enumerate()
+            .flat_map(|(zone_id, zone)| {
+                Self::for_zone(zone)
+                    .positions
+                    .into_iter()
+                    .map(move |(x, y)| (x, (zone_id as f32 + y) / bands))
+            })
+            .collect();
+
+        // Leds that aren't part of any zone are placed along the bottom.
+        let missing = device.colors.len().saturating_sub(positions.len());
+        positions.extend(
+            Self::linear(missing)
+                .positions
+                .into_iter()
+                .map(|(x, _)| (x, 1.0)),
+        );
+        positions.truncate(device.colors.len());
+
+        Self { positions }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    fn spread(index: usize, count: usize) -> f32 {
+        if count <= 1 {
+            0.5
+        } else {
+            index as f32 / (count - 1) as f32
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Software effects rendered on the client and sent to OpenRGB frame by frame.
+//!
+//! ## Example:
+//! ```rust
+//! # use openrgb::{effects::*, *};
+//! # use std::time::Duration;
+//! # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+//! client.get_device(0).await?;
+//!
+//! let mut runner = EffectRunner::new(Rainbow::default(), FrameTarget::Device(0), 30.0);
+//! runner.run(&mut client, Some(Duration::from_secs(10))).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "audio")]
+mod audio;
+mod breathing;
+mod canvas;
+mod compositor;
+mod layout;
+mod rainbow;
+mod runner;
+mod solid;
+mod spectrum;
+mod wave;
+
+#[cfg(feature = "audio")]
+pub use audio::*;
+pub use breathing::*;
+pub use canvas::*;
+pub use compositor::*;
+pub use layout::*;
+pub use rainbow::*;
+pub use runner::*;
+pub use solid::*;
+pub use spectrum::*;
+pub use wave::*;
+
+use crate::Color;
+use std::time::Duration;
+
+pub trait Effect: Send {
+    /// Called with the position of every led before the first frame is rendered, effects that
+    /// depend on geometry should keep a copy. Runners read the layout once when they start, so
+    /// a target that changes shape while running needs to be run again.
+    fn configure(&mut self, _layout: &LedLayout) {}
+
+    /// Renders the frame at `t` since the effect started, `frame` has a color for every led of
+    /// the target in led order.
+    fn render(&mut self, t: Duration, frame: &mut [Color]);
+}
+
+impl<E: Effect + ?Sized> Effect for Box<E> {
+    fn configure(&mut self, layout: &LedLayout) {
+        (**self).configure(layout)
+    }
+
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        (**self).render(t, frame)
+    }
+}
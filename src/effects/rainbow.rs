@@ -0,0 +1,70 @@
+use super::{Effect, LedLayout};
+use crate::Color;
+use std::time::Duration;
+
+/// A rainbow spread horizontally across the leds that scrolls over time.
+#[derive(Debug, Clone)]
+pub struct Rainbow {
+    /// Full rainbows scrolled past per second.
+    pub speed: f32,
+    /// Number of full rainbows across the width of the target.
+    pub scale: f32,
+    pub saturation: f32,
+    pub value: f32,
+    layout: LedLayout,
+}
+
+impl Rainbow {
+    pub fn new(speed: f32, scale: f32) -> Self {
+        Self {
+            speed,
+            scale,
+            saturation: 1.0,
+            value: 1.0,
+            layout: LedLayout::default(),
+        }
+    }
+}
+
+impl Default for Rainbow {
+    fn default() -> Self {
+        Self::new(0.25, 1.0)
+    }
+}
+
+impl Effect for Rainbow {
+    fn configure(&mut self, layout: &LedLayout) {
+        self.layout = layout.clone();
+    }
+
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        let offset = t.as_secs_f32() * self.speed;
+
+        for (led, (x, _)) in frame.iter_mut().zip(&self.layout.positions) {
+            let hue = 360.0 * (x * self.scale - offset);
+            *led = Color::from_hsv(hue, self.saturation, self.value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_and_scrolls_hues() {
+        let mut rainbow = Rainbow::new(0.25, 1.0);
+        rainbow.configure(&LedLayout::linear(5));
+        let mut frame = vec![Color::BLACK; 5];
+
+        rainbow.render(Duration::from_secs(0), &mut frame);
+        assert_eq!(frame[0], Color::RED);
+        assert_eq!(frame[2], Color::CYAN);
+        assert_eq!(frame[4], Color::RED);
+
+        // A second in, the rainbow moved a quarter of the way across.
+        rainbow.render(Duration::from_secs(1), &mut frame);
+        assert_eq!(frame[1], Color::RED);
+        assert_eq!(frame[3], Color::CYAN);
+    }
+}
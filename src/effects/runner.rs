@@ -0,0 +1,104 @@
+use super::{Effect, LedLayout};
+use crate::{Color, FrameTarget, OpenRGBClient, OpenRGBError, OpenRGBResult};
+use std::time::Duration;
+use tokio::time::{interval, Instant, Interval};
+
+/// Paces a render loop at a fixed frame rate.
+pub(crate) struct FrameClock {
+    ticks: Interval,
+    start: Instant,
+    duration: Option<Duration>,
+}
+
+impl FrameClock {
+    /// Starts a clock ticking `fps` times per second for `duration`, or forever if `None`.
+    pub(crate) fn new(fps: f32, duration: Option<Duration>) -> Self {
+        Self {
+            ticks: interval(Duration::from_secs_f32(1.0 / fps.max(f32::EPSILON))),
+            start: Instant::now(),
+            duration,
+        }
+    }
+
+    /// Waits for the next frame and returns the time since the clock started, or `None` once
+    /// the duration has passed.
+    pub(crate) async fn next_frame(&mut self) -> Option<Duration> {
+        self.ticks.tick().await;
+
+        let t = self.start.elapsed();
+        match self.duration {
+            Some(duration) if t >= duration => None,
+            _ => Some(t),
+        }
+    }
+}
+
+/// Drives an effect at a fixed frame rate, sending every frame to a device or zone.
+pub struct EffectRunner<E: Effect> {
+    effect: E,
+    target: FrameTarget,
+    fps: f32,
+}
+
+impl<E: Effect> EffectRunner<E> {
+    pub fn new(effect: E, target: FrameTarget, fps: f32) -> Self {
+        Self {
+            effect,
+            target,
+            fps,
+        }
+    }
+
+    pub fn effect(&mut self) -> &mut E {
+        &mut self.effect
+    }
+
+    pub fn into_inner(self) -> E {
+        self.effect
+    }
+
+    /// Gets the layout of the target's leds from the client's cached copy of the device.
+    pub fn layout(&self, client: &OpenRGBClient) -> OpenRGBResult<LedLayout> {
+        let device_id = self.target.device_id();
+        let device = client
+            .device(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+
+        match self.target {
+            FrameTarget::Device(_) => Ok(LedLayout::for_device(device)),
+            FrameTarget::Zone(_, zone_id) => device
+                .zones
+                .get(zone_id)
+                .map(LedLayout::for_zone)
+                .ok_or(OpenRGBError::InvalidId(zone_id)),
+        }
+    }
+
+    /// Renders and sends frames for `duration`, or forever if `None`.
+    ///
+    /// The target's device has to have been requested through the client first.
+    pub async fn run(
+        &mut self,
+        client: &mut OpenRGBClient,
+        duration: Option<Duration>,
+    ) -> OpenRGBResult<()> {
+        let layout = self.layout(client)?;
+        self.effect.configure(&layout);
+
+        let mut frame = vec![Color::BLACK; layout.len()];
+        let mut clock = FrameClock::new(self.fps, duration);
+
+        while let Some(t) = clock.next_frame().await {
+            self.effect.render(t, &mut frame);
+
+            match self.target {
+                FrameTarget::Device(device_id) => client.update_leds(device_id, &frame).await?,
+                FrameTarget::Zone(device_id, zone_id) => {
+                    client.update_zone_leds(device_id, zone_id, &frame).await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use super::Effect;
+use crate::Color;
+use std::time::Duration;
+
+/// Sets every led to the same color.
+#[derive(Debug, Clone)]
+pub struct Static {
+    pub color: Color,
+}
+
+impl Static {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Effect for Static {
+    fn render(&mut self, _t: Duration, frame: &mut [Color]) {
+        frame.iter_mut().for_each(|led| *led = self.color);
+    }
+}
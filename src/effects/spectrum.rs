@@ -0,0 +1,37 @@
+use super::Effect;
+use crate::Color;
+use std::time::Duration;
+
+/// Cycles every led through all hues in unison.
+#[derive(Debug, Clone)]
+pub struct SpectrumCycle {
+    /// Time it takes to go through every hue once.
+    pub period: Duration,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl SpectrumCycle {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            saturation: 1.0,
+            value: 1.0,
+        }
+    }
+}
+
+impl Default for SpectrumCycle {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+impl Effect for SpectrumCycle {
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        let hue = 360.0 * t.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON);
+        let color = Color::from_hsv(hue, self.saturation, self.value);
+
+        frame.iter_mut().for_each(|led| *led = color);
+    }
+}
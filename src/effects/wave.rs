@@ -0,0 +1,83 @@
+use super::{Effect, LedLayout};
+use crate::Color;
+use std::{f32::consts::PI, time::Duration};
+
+/// Bands of color travelling across the leds over a background.
+#[derive(Debug, Clone)]
+pub struct Wave {
+    pub color: Color,
+    pub background: Color,
+    /// Distance between two bands, where `1.0` is the width of the target.
+    pub wavelength: f32,
+    /// Bands passing a led per second.
+    pub speed: f32,
+    /// Direction the bands travel in, in degrees clockwise from travelling right.
+    pub angle: f32,
+    layout: LedLayout,
+}
+
+impl Wave {
+    pub fn new(color: Color, background: Color) -> Self {
+        Self {
+            color,
+            background,
+            wavelength: 0.5,
+            speed: 1.0,
+            angle: 0.0,
+            layout: LedLayout::default(),
+        }
+    }
+}
+
+impl Effect for Wave {
+    fn configure(&mut self, layout: &LedLayout) {
+        self.layout = layout.clone();
+    }
+
+    fn render(&mut self, t: Duration, frame: &mut [Color]) {
+        let (dir_y, dir_x) = self.angle.to_radians().sin_cos();
+        let wavelength = self.wavelength.max(f32::EPSILON);
+
+        for (led, (x, y)) in frame.iter_mut().zip(&self.layout.positions) {
+            let distance = x * dir_x + y * dir_y;
+            let phase = distance / wavelength - t.as_secs_f32() * self.speed;
+            let intensity = ((2.0 * PI * phase).cos() + 1.0) / 2.0;
+
+            *led = self.background.lerp(self.color, intensity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_travel_across_the_leds() {
+        let mut wave = Wave::new(Color::WHITE, Color::BLACK);
+        wave.configure(&LedLayout::linear(5));
+        let mut frame = vec![Color::BLACK; 5];
+
+        // Bands are half the width apart, so every other quarter is lit.
+        wave.render(Duration::from_secs(0), &mut frame);
+        let (lit, dark) = (Color::WHITE, Color::BLACK);
+        assert_eq!(frame, vec![lit, dark, lit, dark, lit]);
+
+        // Half a band later the dark and lit leds swapped.
+        wave.render(Duration::from_millis(500), &mut frame);
+        assert_eq!(frame, vec![dark, lit, dark, lit, dark]);
+    }
+
+    #[test]
+    fn travels_along_its_angle() {
+        let mut wave = Wave::new(Color::WHITE, Color::BLACK);
+        wave.angle = 90.0;
+        wave.configure(&LedLayout {
+            positions: vec![(0.0, 0.0), (0.5, 0.0), (0.0, 0.25)],
+        });
+        let mut frame = vec![Color::BLACK; 3];
+
+        wave.render(Duration::from_secs(0), &mut frame);
+        assert_eq!(frame, vec![Color::WHITE, Color::WHITE, Color::BLACK]);
+    }
+}
@@ -1,5 +1,5 @@
-use thiserror::Error;
 use crate::command::Command;
+use thiserror::Error;
 
 pub type OpenRGBResult<T> = Result<T, OpenRGBError>;
 
@@ -11,14 +11,60 @@ pub enum OpenRGBError {
     InvalidCommand(u32),
     #[error("invalid element id {0}")]
     InvalidId(usize),
+    #[error("no device matches {0}")]
+    UnknownDevice(String),
+    #[error("more than one device matches {0}")]
+    AmbiguousDevice(String),
+    #[error("zone {0} is not a matrix")]
+    NotAMatrix(usize),
     #[error("incorrect amount of colors expected {0} found {1}")]
     InvalidColorAmount(usize, usize),
+    #[error("incorrect frame size expected {0} bytes found {1}")]
+    InvalidFrameSize(usize, usize),
+    #[error("invalid color {0}")]
+    InvalidColor(String),
+    #[error("invalid key {0}")]
+    InvalidKey(String),
     #[error("invalid mode {0}")]
     InvalidMode(String),
+    #[error("not a valid OpenRGB profile")]
+    InvalidProfile,
+    #[error("unsupported profile version {0}")]
+    UnsupportedProfileVersion(u32),
+    #[error("not a valid packet recording")]
+    InvalidRecording,
+    #[error("replayed client sent command {1} where the recording expected {0}")]
+    ReplayMismatch(u32, u32),
     #[error("string is not valid UTF8 {0}")]
     InvalidUTF8(#[from] std::string::FromUtf8Error),
     #[error("invalid packet body for {0}")]
     InvalidPacketBody(Command),
+    #[error("packet payload of {0} bytes is too large")]
+    PacketTooLarge(u32),
+    // The variants of optional features exist either way, so enabling a feature never changes
+    // the enum. Errors of optional dependencies are boxed for the same reason.
+    #[error("wav error {0}")]
+    Wav(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("http error {0}")]
+    Http(String),
+    #[error("mqtt error {0}")]
+    Mqtt(String),
+    #[error("image error {0}")]
+    Image(#[source] Box<dyn std::error::Error + Send + Sync>),
     #[error("unexpected packet")]
     UnexpectedPacket,
 }
+
+#[cfg(feature = "audio")]
+impl From<hound::Error> for OpenRGBError {
+    fn from(error: hound::Error) -> Self {
+        Self::Wav(Box::new(error))
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for OpenRGBError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(Box::new(error))
+    }
+}
@@ -0,0 +1,575 @@
+mod request;
+mod websocket;
+
+use crate::{Color, OpenRGBClient, OpenRGBDevice, OpenRGBError, OpenRGBResult};
+use request::{Request, Response};
+use serde_json::{json, Value};
+use std::{io::Cursor, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc, Mutex},
+};
+
+/// Serves a small REST and WebSocket API controlling OpenRGB through a client, for dashboards
+/// that don't want to speak the SDK protocol.
+///
+/// | Route | |
+/// |---|---|
+/// | `GET /devices` | Every device with its `id`. |
+/// | `GET /devices/{id}` | A single device. |
+/// | `PUT /devices/{id}/leds` | Sets the leds to `{"colors": ["#FF0000", ...]}` or `{"color": "red"}`. |
+/// | `PUT /devices/{id}/zones/{zone}/leds` | Sets the leds of a zone, like above. |
+/// | `PUT /devices/{id}/mode` | Switches to `{"mode": "Breathing"}`, optionally with `speed`, `direction` and `colors`. |
+/// | `GET /ws` | A WebSocket streaming the device list whenever it changes and accepting led frames. |
+///
+/// Led frames sent over the WebSocket are either text such as
+/// `{"device": 0, "zone": 1, "colors": ["#FF0000", ...]}`, with `zone` being optional, or binary
+/// holding the device id and the zone id as little endian `u32`s followed by the colors as `r`,
+/// `g`, `b` bytes, with `0xFFFFFFFF` as the zone id for the whole device.
+///
+/// Browsers are only allowed to use the API from pages served by the same host or from origins
+/// added with [`HttpServer::with_allowed_origin`], which covers both CORS and the WebSocket.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let client = OpenRGBClient::connect("127.0.0.1:6742", "HTTP").await?;
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+/// HttpServer::new(client).serve(listener).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HttpServer {
+    client: Arc<Mutex<OpenRGBClient>>,
+    poll_interval: Duration,
+    allowed_origins: Arc<Vec<String>>,
+}
+
+impl HttpServer {
+    pub fn new(client: OpenRGBClient) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+            poll_interval: Duration::from_secs(2),
+            allowed_origins: Arc::default(),
+        }
+    }
+
+    /// Sets how often OpenRGB is asked whether the device list changed, two seconds by
+    /// default. The list itself is only requested again once it did.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Allows pages from the origin, such as `http://dashboard.local:3000`, to use the API and
+    /// the WebSocket. `*` allows every origin.
+    pub fn with_allowed_origin<S: Into<String>>(mut self, origin: S) -> Self {
+        Arc::make_mut(&mut self.allowed_origins).push(origin.into());
+        self
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> OpenRGBResult<()> {
+        let (updates, _) = broadcast::channel(16);
+
+        let accept = async {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let client = self.client.clone();
+                let origins = self.allowed_origins.clone();
+                let updates = updates.subscribe();
+
+                tokio::spawn(async move {
+                    let _ = serve_connection(stream, client, &origins, updates).await;
+                });
+            }
+        };
+
+        // OpenRGB's notifications are only read while the client waits for an answer, so a
+        // cheap count request picks them up. The list is only requested once something changed.
+        let poll = async {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            let mut last_count = None;
+            let mut last = None;
+
+            loop {
+                interval.tick().await;
+
+                let mut client = self.client.lock().await;
+                let count = match client.get_device_count().await {
+                    Ok(count) => count,
+                    Err(_) => continue,
+                };
+                if !client.take_devices_changed() && last_count == Some(count) {
+                    continue;
+                }
+
+                let devices = match client.get_all_devices().await {
+                    Ok(devices) => devices,
+                    Err(_) => continue,
+                };
+                drop(client);
+
+                last_count = Some(count);
+                let summary = device_list(&devices);
+
+                if last.as_ref() != Some(&summary) {
+                    let _ = updates.send(summary.to_string());
+                    last = Some(summary);
+                }
+            }
+        };
+
+        tokio::select! {
+            result = accept => result,
+            _ = poll => Ok(()),
+        }
+    }
+}
+
+/// The devices as sent over the WebSocket, leaving out colors and modes so only changes to
+/// the list itself are sent.
+fn device_list(devices: &[OpenRGBDevice]) -> Value {
+    let devices: Vec<Value> = devices
+        .iter()
+        .enumerate()
+        .map(|(id, device)| {
+            json!({
+                "id": id,
+                "name": device.name,
+                "serial": device.serial,
+                "location": device.location,
+                "leds": device.leds.len(),
+                "zones": device.zones.iter().map(|zone| json!({
+                    "name": zone.name,
+                    "leds": zone.leds_count,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    json!({ "type": "devices", "devices": devices })
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    client: Arc<Mutex<OpenRGBClient>>,
+    allowed_origins: &[String],
+    updates: broadcast::Receiver<String>,
+) -> OpenRGBResult<()> {
+    let mut buffer = Vec::new();
+
+    loop {
+        let request = match Request::read(&mut stream, &mut buffer).await {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(OpenRGBError::Http(message)) => {
+                return Response::error(400, &message).write(&mut stream).await;
+            }
+            Err(error) => return Err(error),
+        };
+
+        let origin = request.header("origin");
+        let allowed_origin = origin.filter(|origin| origin_allowed(allowed_origins, origin));
+
+        if request.method == "GET"
+            && path(&request) == "/ws"
+            && request.has_token("upgrade", "websocket")
+        {
+            // Browsers let any page open a WebSocket, only the origin tells them apart.
+            if origin.is_some() && allowed_origin.is_none() && !same_origin(&request) {
+                return Response::error(403, "origin not allowed")
+                    .write(&mut stream)
+                    .await;
+            }
+
+            return serve_websocket(stream, request, buffer, client, updates).await;
+        }
+
+        let mut response = match route(&client, &request).await {
+            Ok(response) | Err(response) => response,
+        };
+        if let Some(origin) = allowed_origin {
+            response = response
+                .with_header("Access-Control-Allow-Origin", origin)
+                .with_header("Vary", "Origin");
+        }
+        response.write(&mut stream).await?;
+
+        if request.has_token("connection", "close") {
+            return Ok(());
+        }
+    }
+}
+
+fn origin_allowed(allowed_origins: &[String], origin: &str) -> bool {
+    allowed_origins
+        .iter()
+        .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+}
+
+/// Returns `true` if the request comes from a page served by the host it was sent to.
+fn same_origin(request: &Request) -> bool {
+    match (request.header("origin"), request.header("host")) {
+        (Some(origin), Some(host)) => {
+            let origin = origin
+                .strip_prefix("http://")
+                .or_else(|| origin.strip_prefix("https://"));
+            match origin {
+                Some(origin) => origin.eq_ignore_ascii_case(host.trim()),
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn path(request: &Request) -> &str {
+    request.path.split('?').next().unwrap_or_default()
+}
+
+async fn route(client: &Mutex<OpenRGBClient>, request: &Request) -> Result<Response, Response> {
+    let segments: Vec<&str> = path(request)
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    if request.method == "OPTIONS" {
+        return Ok(Response::new(204)
+            .with_header("Access-Control-Allow-Methods", "GET, PUT, OPTIONS")
+            .with_header("Access-Control-Allow-Headers", "Content-Type"));
+    }
+
+    let id = |index: usize| -> Result<usize, Response> {
+        segments[index]
+            .parse()
+            .map_err(|_| Response::error(404, "not found"))
+    };
+
+    let mut client = client.lock().await;
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["devices"]) => {
+            let devices = client.get_all_devices().await?;
+            let devices: Vec<Value> = devices
+                .iter()
+                .enumerate()
+                .map(|(id, device)| device_json(id, device))
+                .collect();
+            Ok(Response::json(200, &Value::Array(devices)))
+        }
+        ("GET", ["devices", _]) => {
+            let device_id = id(1)?;
+            let device = fresh_device(&mut client, device_id).await?;
+            Ok(Response::json(200, &device_json(device_id, &device)))
+        }
+        ("PUT", ["devices", _, "leds"]) => {
+            let colors = request.json()?;
+            update_leds(&mut client, id(1)?, None, LedColors::from_json(&colors)?).await?;
+            Ok(Response::new(204))
+        }
+        ("PUT", ["devices", _, "zones", _, "leds"]) => {
+            let colors = request.json()?;
+            update_leds(
+                &mut client,
+                id(1)?,
+                Some(id(3)?),
+                LedColors::from_json(&colors)?,
+            )
+            .await?;
+            Ok(Response::new(204))
+        }
+        ("PUT", ["devices", _, "mode"]) => {
+            update_mode(&mut client, id(1)?, &request.json()?).await?;
+            Ok(Response::new(204))
+        }
+        (_, ["devices"])
+        | (_, ["devices", _])
+        | (_, ["devices", _, "leds"])
+        | (_, ["devices", _, "zones", _, "leds"])
+        | (_, ["devices", _, "mode"]) => Err(Response::error(405, "method not allowed")),
+        _ => Err(Response::error(404, "not found")),
+    }
+}
+
+impl From<OpenRGBError> for Response {
+    fn from(error: OpenRGBError) -> Self {
+        let status = match error {
+            OpenRGBError::InvalidId(_)
+            | OpenRGBError::UnknownDevice(_)
+            | OpenRGBError::AmbiguousDevice(_) => 404,
+            OpenRGBError::InvalidColor(_)
+            | OpenRGBError::InvalidColorAmount(_, _)
+            | OpenRGBError::InvalidMode(_)
+            | OpenRGBError::NotAMatrix(_) => 400,
+            _ => 502,
+        };
+
+        Response::error(status, &error.to_string())
+    }
+}
+
+fn device_json(device_id: usize, device: &OpenRGBDevice) -> Value {
+    let mut value = serde_json::to_value(device).unwrap_or(Value::Null);
+    if let Value::Object(object) = &mut value {
+        object.insert("id".to_string(), json!(device_id));
+    }
+    value
+}
+
+/// Requests a device, making sure it exists first since OpenRGB doesn't answer requests for
+/// devices it doesn't have.
+async fn fresh_device(
+    client: &mut OpenRGBClient,
+    device_id: usize,
+) -> OpenRGBResult<OpenRGBDevice> {
+    if device_id >= client.get_device_count().await? {
+        return Err(OpenRGBError::InvalidId(device_id));
+    }
+
+    client.get_device(device_id).await
+}
+
+/// Like [`fresh_device`], but only requests the device if it isn't cached, so led updates
+/// don't cost a round trip each.
+async fn cached_device(client: &mut OpenRGBClient, device_id: usize) -> OpenRGBResult<()> {
+    if client.device(device_id).is_none() {
+        fresh_device(client, device_id).await?;
+    }
+
+    Ok(())
+}
+
+enum LedColors {
+    Each(Vec<Color>),
+    All(Color),
+}
+
+impl LedColors {
+    fn from_json(body: &Value) -> OpenRGBResult<Self> {
+        let parse = |value: &Value| -> OpenRGBResult<Color> {
+            value
+                .as_str()
+                .ok_or_else(|| OpenRGBError::InvalidColor(value.to_string()))?
+                .parse()
+        };
+
+        if let Some(colors) = body.get("colors").and_then(Value::as_array) {
+            colors
+                .iter()
+                .map(parse)
+                .collect::<OpenRGBResult<_>>()
+                .map(Self::Each)
+        } else if let Some(color) = body.get("color") {
+            parse(color).map(Self::All)
+        } else {
+            Err(OpenRGBError::InvalidColor(body.to_string()))
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self::Each(
+            bytes
+                .chunks_exact(3)
+                .map(|color| Color::new(color[0], color[1], color[2]))
+                .collect(),
+        )
+    }
+
+    fn resolve(self, count: usize) -> Vec<Color> {
+        match self {
+            Self::Each(colors) => colors,
+            Self::All(color) => vec![color; count],
+        }
+    }
+}
+
+async fn update_leds(
+    client: &mut OpenRGBClient,
+    device_id: usize,
+    zone_id: Option<usize>,
+    colors: LedColors,
+) -> OpenRGBResult<()> {
+    cached_device(client, device_id).await?;
+    let device = client
+        .device(device_id)
+        .ok_or(OpenRGBError::InvalidId(device_id))?;
+
+    match zone_id {
+        Some(zone_id) => {
+            let count = device
+                .zones
+                .get(zone_id)
+                .ok_or(OpenRGBError::InvalidId(zone_id))?
+                .leds_count as usize;
+            let colors = colors.resolve(count);
+            client.update_zone_leds(device_id, zone_id, &colors).await
+        }
+        None => {
+            let colors = colors.resolve(device.colors.len());
+            client.update_leds(device_id, &colors).await
+        }
+    }
+}
+
+async fn update_mode(
+    client: &mut OpenRGBClient,
+    device_id: usize,
+    body: &Value,
+) -> OpenRGBResult<()> {
+    let device = fresh_device(client, device_id).await?;
+    let name = body
+        .get("mode")
+        .and_then(Value::as_str)
+        .ok_or_else(|| OpenRGBError::InvalidMode(body.to_string()))?;
+
+    let mut mode = device
+        .modes
+        .iter()
+        .find(|mode| mode.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| OpenRGBError::InvalidMode(name.to_string()))?;
+
+    if let Some(speed) = body.get("speed").and_then(Value::as_u64) {
+        mode.speed = (speed.min(u32::MAX as u64) as u32).clamp(
+            mode.speed_min.min(mode.speed_max),
+            mode.speed_max.max(mode.speed_min),
+        );
+    }
+
+    if let Some(direction) = body.get("direction").and_then(Value::as_u64) {
+        mode.direction = direction.min(u32::MAX as u64) as u32;
+    }
+
+    if body.get("colors").is_some() {
+        mode.colors = LedColors::from_json(body)?.resolve(0);
+    }
+
+    client.update_mode(device_id, &mode).await
+}
+
+async fn serve_websocket(
+    mut stream: TcpStream,
+    request: Request,
+    buffered: Vec<u8>,
+    client: Arc<Mutex<OpenRGBClient>>,
+    mut updates: broadcast::Receiver<String>,
+) -> OpenRGBResult<()> {
+    let key = match request.header("sec-websocket-key") {
+        Some(key) => key,
+        None => {
+            return Response::error(400, "missing Sec-WebSocket-Key")
+                .write(&mut stream)
+                .await
+        }
+    };
+
+    Response::new(101)
+        .with_header("Upgrade", "websocket")
+        .with_header("Connection", "Upgrade")
+        .with_header("Sec-WebSocket-Accept", websocket::accept_key(key))
+        .write(&mut stream)
+        .await?;
+
+    let (reader, mut writer) = stream.into_split();
+
+    // Frames are read on their own task, as reading one can't be interrupted by an update.
+    let (sender, mut messages) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut reader = Cursor::new(buffered).chain(reader);
+        let mut fragments = None;
+
+        loop {
+            let message = websocket::read_message(&mut reader, &mut fragments).await;
+            let failed = message.is_err();
+            if sender.send(message).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let devices = client.lock().await.get_all_devices().await?;
+    let list = device_list(&devices).to_string();
+    writer
+        .write_all(&websocket::frame(websocket::TEXT, list.as_bytes()))
+        .await?;
+
+    loop {
+        tokio::select! {
+            message = messages.recv() => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    _ => return Ok(()),
+                };
+
+                match message.opcode {
+                    websocket::TEXT | websocket::BINARY => {
+                        if let Err(error) = led_frame(&client, &message).await {
+                            let error = json!({ "type": "error", "message": error.to_string() });
+                            let error = error.to_string();
+                            writer
+                                .write_all(&websocket::frame(websocket::TEXT, error.as_bytes()))
+                                .await?;
+                        }
+                    }
+                    websocket::PING => {
+                        writer
+                            .write_all(&websocket::frame(websocket::PONG, &message.payload))
+                            .await?;
+                    }
+                    websocket::CLOSE => {
+                        writer.write_all(&websocket::frame(websocket::CLOSE, &[])).await?;
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    writer
+                        .write_all(&websocket::frame(websocket::TEXT, update.as_bytes()))
+                        .await?
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+async fn led_frame(
+    client: &Mutex<OpenRGBClient>,
+    message: &websocket::Message,
+) -> OpenRGBResult<()> {
+    let (device_id, zone_id, colors) = if message.opcode == websocket::TEXT {
+        let frame: Value = serde_json::from_slice(&message.payload)
+            .map_err(|error| OpenRGBError::Http(error.to_string()))?;
+        let device_id = frame
+            .get("device")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| OpenRGBError::Http("missing device".to_string()))?;
+        let zone_id = frame.get("zone").and_then(Value::as_u64);
+
+        (
+            device_id as usize,
+            zone_id.map(|zone_id| zone_id as usize),
+            LedColors::from_json(&frame)?,
+        )
+    } else {
+        let header = message
+            .payload
+            .get(..8)
+            .ok_or_else(|| OpenRGBError::Http("led frame too short".to_string()))?;
+        let device_id = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let zone_id = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        (
+            device_id as usize,
+            Some(zone_id as usize).filter(|_| zone_id != u32::MAX),
+            LedColors::from_bytes(&message.payload[8..]),
+        )
+    };
+
+    let mut client = client.lock().await;
+    update_leds(&mut client, device_id, zone_id, colors).await
+}
@@ -0,0 +1,296 @@
+use crate::{OpenRGBError, OpenRGBResult};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads the next request, returning `None` if the connection was closed between requests.
+    ///
+    /// Bytes read past the end of the request are kept in `buffer` for the next one.
+    pub async fn read<R: AsyncRead + Unpin + Send>(
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> OpenRGBResult<Option<Self>> {
+        loop {
+            let mut headers = [httparse::EMPTY_HEADER; 32];
+            let mut request = httparse::Request::new(&mut headers);
+
+            let status = request
+                .parse(buffer)
+                .map_err(|error| OpenRGBError::Http(error.to_string()))?;
+
+            if let httparse::Status::Complete(head_length) = status {
+                let headers: Vec<(String, String)> = request
+                    .headers
+                    .iter()
+                    .map(|header| {
+                        (
+                            header.name.to_ascii_lowercase(),
+                            String::from_utf8_lossy(header.value).into_owned(),
+                        )
+                    })
+                    .collect();
+                let method = request.method.unwrap_or_default().to_string();
+                let path = request.path.unwrap_or_default().to_string();
+
+                let body_length = match header(&headers, "content-length") {
+                    Some(length) => length
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|length| *length <= MAX_BODY)
+                        .ok_or_else(|| OpenRGBError::Http("invalid content length".to_string()))?,
+                    None => 0,
+                };
+
+                while buffer.len() < head_length + body_length {
+                    if read_more(reader, buffer).await? == 0 {
+                        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                }
+
+                let body = buffer[head_length..head_length + body_length].to_vec();
+                buffer.drain(..head_length + body_length);
+
+                return Ok(Some(Self {
+                    method,
+                    path,
+                    headers,
+                    body,
+                }));
+            }
+
+            if buffer.len() > MAX_HEAD {
+                return Err(OpenRGBError::Http("request head too large".to_string()));
+            }
+
+            if read_more(reader, buffer).await? == 0 {
+                return match buffer.is_empty() {
+                    true => Ok(None),
+                    false => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                };
+            }
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// Returns `true` if the header holds the token in its comma separated list, ignoring case.
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        match self.header(name) {
+            Some(value) => value
+                .split(',')
+                .any(|candidate| candidate.trim().eq_ignore_ascii_case(token)),
+            None => false,
+        }
+    }
+
+    pub fn json(&self) -> Result<Value, Response> {
+        serde_json::from_slice(&self.body)
+            .map_err(|error| Response::error(400, &format!("invalid json: {}", error)))
+    }
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+async fn read_more<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+) -> OpenRGBResult<usize> {
+    let mut chunk = [0u8; 4096];
+    let read = reader.read(&mut chunk).await?;
+    buffer.extend_from_slice(&chunk[..read]);
+    Ok(read)
+}
+
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn json(status: u16, value: &Value) -> Self {
+        Self {
+            body: value.to_string().into_bytes(),
+            ..Self::new(status).with_header("Content-Type", "application/json")
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    pub fn with_header<S: Into<String>>(mut self, name: &'static str, value: S) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> OpenRGBResult<()> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+
+        // Switching protocols hands the connection over to WebSocket, which has no body.
+        if self.status != 101 {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("\r\n");
+
+        let mut response = head.into_bytes();
+        response.extend_from_slice(&self.body);
+        writer.write_all(&response).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        101 => "Switching Protocols",
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(data: &[u8]) -> OpenRGBResult<Option<Request>> {
+        Request::read(&mut &data[..], &mut Vec::new()).await
+    }
+
+    #[tokio::test]
+    async fn reads_requests() {
+        let data = b"PUT /devices/0/leds HTTP/1.1\r\nHost: x\r\nContent-Length: 15\r\n\r\n{\"color\":\"red\"}GET / HTTP/1.1\r\n\r\n";
+        let mut reader = &data[..];
+        let mut buffer = Vec::new();
+
+        let request = Request::read(&mut reader, &mut buffer)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/devices/0/leds");
+        assert_eq!(request.header("host"), Some("x"));
+        assert_eq!(request.json().unwrap()["color"], "red");
+
+        // The next request was read along with the first one.
+        let request = Request::read(&mut reader, &mut buffer)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("GET", "/")
+        );
+        assert!(request.body.is_empty());
+
+        assert!(Request::read(&mut reader, &mut buffer)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_requests() {
+        let body = format!("PUT / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert!(matches!(
+            read(body.as_bytes()).await,
+            Err(OpenRGBError::Http(_))
+        ));
+
+        let head = format!("GET /{} HTTP/1.1\r\n", "a".repeat(MAX_HEAD + 1));
+        assert!(matches!(
+            read(head.as_bytes()).await,
+            Err(OpenRGBError::Http(_))
+        ));
+
+        assert!(matches!(
+            read(b"PUT / HTTP/1.1\r\nContent-Length: x\r\n\r\n").await,
+            Err(OpenRGBError::Http(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_requests() {
+        assert!(matches!(
+            read(b"GET / HTTP/1.1\r\n").await,
+            Err(OpenRGBError::IO(_))
+        ));
+        assert!(matches!(
+            read(b"PUT / HTTP/1.1\r\nContent-Length: 4\r\n\r\n{}").await,
+            Err(OpenRGBError::IO(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn finds_header_tokens() {
+        let request = read(b"GET /ws HTTP/1.1\r\nConnection: keep-alive, Upgrade\r\n\r\n")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(request.has_token("connection", "upgrade"));
+        assert!(!request.has_token("connection", "close"));
+        assert!(!request.has_token("upgrade", "websocket"));
+    }
+
+    #[tokio::test]
+    async fn writes_responses() {
+        let mut data = Vec::new();
+        Response::error(404, "not found")
+            .write(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 21\r\n\r\n{\"error\":\"not found\"}"
+        );
+
+        let mut data = Vec::new();
+        Response::new(101)
+            .with_header("Upgrade", "websocket")
+            .write(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n"
+        );
+    }
+}
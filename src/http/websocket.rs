@@ -0,0 +1,226 @@
+use crate::{OpenRGBError, OpenRGBResult};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_MESSAGE: u64 = 1024 * 1024;
+
+pub(crate) const TEXT: u8 = 0x1;
+pub(crate) const BINARY: u8 = 0x2;
+pub(crate) const CLOSE: u8 = 0x8;
+pub(crate) const PING: u8 = 0x9;
+pub(crate) const PONG: u8 = 0xA;
+
+/// The `Sec-WebSocket-Accept` answering a `Sec-WebSocket-Key`.
+pub(crate) fn accept_key(key: &str) -> String {
+    let digest = sha1::Sha1::from(format!("{}{}", key.trim(), GUID)).digest();
+    base64::encode(digest.bytes())
+}
+
+#[derive(Debug)]
+pub(crate) struct Message {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Reads the next message, joining fragmented frames. Control frames arriving between
+/// fragments are returned first.
+///
+/// Clients have to mask every frame and finish a fragmented message before starting the next,
+/// anything else fails the connection.
+pub(crate) async fn read_message<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+    fragments: &mut Option<Message>,
+) -> OpenRGBResult<Message> {
+    loop {
+        let first = reader.read_u8().await?;
+        let second = reader.read_u8().await?;
+        let fin = first & 0x80 != 0;
+        let opcode = first & 0x0F;
+
+        let length = match second & 0x7F {
+            126 => reader.read_u16().await? as u64,
+            127 => reader.read_u64().await?,
+            length => length as u64,
+        };
+        if length > MAX_MESSAGE {
+            return Err(OpenRGBError::Http(
+                "websocket message too large".to_string(),
+            ));
+        }
+
+        if second & 0x80 == 0 {
+            return Err(OpenRGBError::Http("unmasked websocket frame".to_string()));
+        }
+
+        let mut mask = [0u8; 4];
+        reader.read_exact(&mut mask).await?;
+
+        let mut payload = vec![0u8; length as usize];
+        reader.read_exact(&mut payload).await?;
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+
+        if opcode >= CLOSE {
+            return Ok(Message { opcode, payload });
+        }
+
+        let message = match (opcode, fragments.take()) {
+            (0, Some(mut message)) => {
+                message.payload.extend_from_slice(&payload);
+                message
+            }
+            (0, None) => return Err(OpenRGBError::Http("unexpected continuation".to_string())),
+            (_, Some(_)) => {
+                return Err(OpenRGBError::Http(
+                    "new message before the last one finished".to_string(),
+                ))
+            }
+            (opcode, None) => Message { opcode, payload },
+        };
+
+        if message.payload.len() as u64 > MAX_MESSAGE {
+            return Err(OpenRGBError::Http(
+                "websocket message too large".to_string(),
+            ));
+        }
+
+        if fin {
+            return Ok(message);
+        }
+
+        *fragments = Some(message);
+    }
+}
+
+/// Encodes an unmasked frame, as sent by servers.
+pub(crate) fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend_from_slice(payload);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a frame the way a client does, with the payload masked.
+    fn client_frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xFA, 0x21, 0x3D];
+        let mut frame = super::frame(0, payload);
+        frame[0] = first;
+        frame[1] |= 0x80;
+
+        let start = frame.len() - payload.len();
+        for (index, byte) in frame[start..].iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+        frame.splice(start..start, mask.iter().copied());
+        frame
+    }
+
+    async fn read(data: &[u8]) -> OpenRGBResult<Message> {
+        read_message(&mut &data[..], &mut None).await
+    }
+
+    #[test]
+    fn accepts_keys() {
+        // The example from RFC 6455.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[tokio::test]
+    async fn unmasks_messages() {
+        let message = read(&client_frame(0x80 | TEXT, b"Hello")).await.unwrap();
+        assert_eq!(message.opcode, TEXT);
+        assert_eq!(message.payload, b"Hello");
+
+        // Only servers may send unmasked frames.
+        assert!(matches!(
+            read(&frame(BINARY, &[1, 2, 3])).await,
+            Err(OpenRGBError::Http(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reads_extended_lengths() {
+        for length in &[125, 126, 65535, 65536] {
+            let payload = vec![9u8; *length];
+            let message = read(&client_frame(0x80 | BINARY, &payload)).await.unwrap();
+            assert_eq!(message.payload, payload);
+        }
+    }
+
+    #[test]
+    fn encodes_extended_lengths() {
+        assert_eq!(&frame(TEXT, &[0; 125])[..2], &[0x81, 125]);
+        assert_eq!(&frame(TEXT, &[0; 126])[..4], &[0x81, 126, 0, 126]);
+        assert_eq!(&frame(TEXT, &[0; 65535])[..4], &[0x81, 126, 0xFF, 0xFF]);
+        assert_eq!(
+            &frame(BINARY, &[0; 65536])[..10],
+            &[0x82, 127, 0, 0, 0, 0, 0, 1, 0, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_message_size() {
+        // Refused from the header alone, before the payload is read.
+        let mut huge = vec![0x80 | BINARY, 127];
+        huge.extend_from_slice(&(MAX_MESSAGE + 1).to_be_bytes());
+        assert!(matches!(read(&huge).await, Err(OpenRGBError::Http(_))));
+
+        // Fragments adding up to more than the limit.
+        let half = vec![0u8; MAX_MESSAGE as usize / 2 + 1];
+        let mut fragmented = client_frame(BINARY, &half);
+        fragmented.extend(client_frame(0x80, &half));
+        assert!(matches!(
+            read(&fragmented).await,
+            Err(OpenRGBError::Http(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn joins_fragments() {
+        let mut data = client_frame(TEXT, b"Hel");
+        data.extend(client_frame(0x80 | PING, b"?"));
+        data.extend(client_frame(0x80, b"lo"));
+
+        let mut reader = &data[..];
+        let mut fragments = None;
+
+        let ping = read_message(&mut reader, &mut fragments).await.unwrap();
+        assert_eq!((ping.opcode, ping.payload), (PING, b"?".to_vec()));
+
+        let message = read_message(&mut reader, &mut fragments).await.unwrap();
+        assert_eq!((message.opcode, message.payload), (TEXT, b"Hello".to_vec()));
+
+        assert!(matches!(
+            read(&client_frame(0x80, b"lo")).await,
+            Err(OpenRGBError::Http(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_interleaved_messages() {
+        let mut data = client_frame(TEXT, b"Hel");
+        data.extend(client_frame(0x80 | BINARY, &[1, 2, 3]));
+
+        assert!(matches!(read(&data).await, Err(OpenRGBError::Http(_))));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::OpenRGBDevice;
+use std::fmt::Display;
+
+/// Identifies a device independently of its position in OpenRGB's device list, which changes
+/// when hardware is added or OpenRGB restarts.
+///
+/// Only `name` is required, any other field that is set must match the device as well. Keys
+/// created from a device fill in every field the device reports. The vendor isn't part of the
+/// key as it isn't sent by the version of the protocol this crate implements.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+/// let keyboard = DeviceKey::new("Corsair K70 RGB MK.2");
+/// let device_id = client.resolve(&keyboard).await?;
+/// client.update_leds(device_id, &[Color::RED]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceKey {
+    pub name: String,
+    pub serial: Option<String>,
+    pub location: Option<String>,
+}
+
+impl DeviceKey {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            serial: None,
+            location: None,
+        }
+    }
+
+    pub fn with_serial<S: Into<String>>(mut self, serial: S) -> Self {
+        self.serial = Some(serial.into());
+        self
+    }
+
+    pub fn with_location<S: Into<String>>(mut self, location: S) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Returns `true` if every field of the key matches the device.
+    pub fn matches(&self, device: &OpenRGBDevice) -> bool {
+        self.matches_loosely(device) && Self::field_matches(&self.location, &device.location)
+    }
+
+    /// Returns `true` if the key matches the device ignoring its location, for devices that
+    /// have been moved to a different port.
+    pub fn matches_loosely(&self, device: &OpenRGBDevice) -> bool {
+        self.name == device.name && Self::field_matches(&self.serial, &device.serial)
+    }
+
+    fn field_matches(expected: &Option<String>, actual: &str) -> bool {
+        match expected {
+            Some(expected) => expected == actual,
+            None => true,
+        }
+    }
+}
+
+impl From<&OpenRGBDevice> for DeviceKey {
+    fn from(device: &OpenRGBDevice) -> Self {
+        let non_empty = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+
+        Self {
+            name: device.name.clone(),
+            serial: non_empty(&device.serial),
+            location: non_empty(&device.location),
+        }
+    }
+}
+
+impl Display for DeviceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if let Some(serial) = &self.serial {
+            write!(f, " [{}]", serial)?;
+        }
+
+        if let Some(location) = &self.location {
+            write!(f, " @ {}", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::FakeServer, types::tests::device, OpenRGBError};
+
+    #[test]
+    fn matches_every_given_field() {
+        let strip = device("Strip", "1234");
+
+        assert!(DeviceKey::new("Strip").matches(&strip));
+        assert!(DeviceKey::new("Strip").with_serial("1234").matches(&strip));
+        assert!(DeviceKey::from(&strip).matches(&strip));
+        assert!(!DeviceKey::new("Fan").matches(&strip));
+        assert!(!DeviceKey::new("Strip").with_serial("5678").matches(&strip));
+        assert!(!DeviceKey::new("Strip")
+            .with_location("HID: /dev/hidraw1")
+            .matches(&strip));
+    }
+
+    #[test]
+    fn loose_matches_ignore_the_location() {
+        let strip = device("Strip", "1234");
+        let moved = DeviceKey::from(&strip).with_location("HID: /dev/hidraw1");
+
+        assert!(!moved.matches(&strip));
+        assert!(moved.matches_loosely(&strip));
+        assert!(!moved.with_serial("5678").matches_loosely(&strip));
+    }
+
+    #[test]
+    fn keys_leave_out_empty_fields() {
+        let mut strip = device("Strip", "");
+        strip.location = String::new();
+
+        assert_eq!(DeviceKey::from(&strip), DeviceKey::new("Strip"));
+        assert_eq!(
+            DeviceKey::from(&device("Strip", "1234")).to_string(),
+            "Strip [1234] @ HID: /dev/hidraw0"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolves_devices_by_key() {
+        let mut moved = device("Strip", "5678");
+        moved.location = "HID: /dev/hidraw1".to_string();
+        let devices = vec![device("Strip", "1234"), moved, device("Fan", "")];
+        let (mut client, _server) = FakeServer::connect(devices).await;
+
+        // Exact serial, even with duplicate names.
+        let key = DeviceKey::new("Strip").with_serial("5678");
+        assert_eq!(client.resolve(&key).await.unwrap(), 1);
+
+        // Found by its serial after moving to another location.
+        let key = key.with_location("HID: /dev/hidraw2");
+        assert_eq!(client.resolve(&key).await.unwrap(), 1);
+
+        // Without a serial the location has to match.
+        let key = DeviceKey::new("Fan").with_location("HID: /dev/hidraw0");
+        assert_eq!(client.resolve(&key).await.unwrap(), 2);
+        let key = DeviceKey::new("Fan").with_location("HID: /dev/hidraw2");
+        assert!(matches!(
+            client.resolve(&key).await,
+            Err(OpenRGBError::UnknownDevice(_))
+        ));
+
+        assert!(matches!(
+            client.resolve(&DeviceKey::new("Strip")).await,
+            Err(OpenRGBError::AmbiguousDevice(_))
+        ));
+    }
+}
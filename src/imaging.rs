@@ -0,0 +1,239 @@
+use crate::{Color, MatrixFrame, OpenRGBClient, OpenRGBMatrixMap, OpenRGBResult};
+use image::{
+    codecs::gif::GifDecoder, imageops::FilterType, AnimationDecoder, DynamicImage, RgbaImage,
+};
+use std::{fs::File, io::BufReader, io::Read, path::Path, time::Duration};
+use tokio::time::sleep;
+
+// Browsers treat GIF frames with (almost) no delay as having this delay, so do we.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Scales the image to the dimensions of the matrix and samples a color for every cell.
+///
+/// Mostly transparent pixels are left unpainted so their leds keep their current color.
+pub fn image_to_frame(image: &DynamicImage, map: &OpenRGBMatrixMap) -> MatrixFrame {
+    rgba_to_frame(&image.to_rgba8(), map)
+}
+
+fn rgba_to_frame(image: &RgbaImage, map: &OpenRGBMatrixMap) -> MatrixFrame {
+    let mut frame = MatrixFrame::for_map(map);
+
+    if map.width == 0 || map.height == 0 {
+        return frame;
+    }
+
+    let scaled = image::imageops::resize(image, map.width, map.height, FilterType::Triangle);
+
+    for (x, y, pixel) in scaled.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+
+        if a >= 0x80 {
+            frame.set(x, y, Color::new(r, g, b));
+        }
+    }
+
+    frame
+}
+
+/// A decoded animation whose frames can be played on matrix zones.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<(RgbaImage, Duration)>,
+}
+
+impl Animation {
+    /// Decodes every frame of a GIF along with its delay.
+    pub fn from_gif<R: Read>(reader: R) -> OpenRGBResult<Self> {
+        let frames = GifDecoder::new(reader)?
+            .into_frames()
+            .collect_frames()?
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let delay = Duration::from_millis((numer / denom.max(1)) as u64);
+                let delay = if delay < Duration::from_millis(20) {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    delay
+                };
+
+                (frame.into_buffer(), delay)
+            })
+            .collect();
+
+        Ok(Self { frames })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> OpenRGBResult<Self> {
+        Self::from_gif(BufReader::new(File::open(path)?))
+    }
+
+    /// Creates a single frame animation from a still image.
+    pub fn from_image(image: &DynamicImage) -> Self {
+        Self {
+            frames: vec![(image.to_rgba8(), DEFAULT_FRAME_DELAY)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Total duration of a single loop of the animation.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|(_, delay)| *delay).sum()
+    }
+
+    /// Scales every frame to the dimensions of the matrix, returning each with its delay.
+    pub fn frames_for(&self, map: &OpenRGBMatrixMap) -> Vec<(MatrixFrame, Duration)> {
+        self.frames
+            .iter()
+            .map(|(image, delay)| (rgba_to_frame(image, map), *delay))
+            .collect()
+    }
+}
+
+impl OpenRGBClient {
+    /// Displays the image on a matrix zone, see [`image_to_frame`].
+    pub async fn update_matrix_image(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        image: &DynamicImage,
+    ) -> OpenRGBResult<()> {
+        let map = self.matrix_map(device_id, zone_id)?.clone();
+        self.update_matrix(device_id, zone_id, &image_to_frame(image, &map))
+            .await
+    }
+
+    /// Plays the animation on a matrix zone, respecting the delay of every frame.
+    ///
+    /// The animation is played `loops` times, or forever if `None`.
+    pub async fn play_animation(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        animation: &Animation,
+        loops: Option<usize>,
+    ) -> OpenRGBResult<()> {
+        let frames = animation.frames_for(self.matrix_map(device_id, zone_id)?);
+
+        let mut played = 0;
+        while !frames.is_empty() && loops != Some(played) {
+            for (frame, delay) in &frames {
+                self.update_matrix(device_id, zone_id, frame).await?;
+                sleep(*delay).await;
+            }
+
+            played += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NO_LED;
+    use image::{codecs::gif::GifEncoder, Delay, Frame, Rgba};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// An image that is red on the left half and blue on the right half.
+    fn halves(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, _| if x < width / 2 { RED } else { BLUE })
+    }
+
+    fn is_mostly(color: Option<Color>, expected: Color) -> bool {
+        match color {
+            Some(color) => {
+                let distance = |a: u8, b: u8| (a as i32 - b as i32).abs();
+                distance(color.r, expected.r) + distance(color.b, expected.b) < 128
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn scales_images_onto_the_matrix() {
+        // Two leds with a hole between them.
+        let map = OpenRGBMatrixMap {
+            height: 1,
+            width: 3,
+            map: vec![0, NO_LED, 1],
+        };
+
+        let frame = image_to_frame(&DynamicImage::ImageRgba8(halves(6, 2)), &map);
+        assert_eq!((frame.width(), frame.height()), (3, 1));
+        assert!(is_mostly(frame.get(0, 0), Color::RED));
+        assert!(is_mostly(frame.get(2, 0), Color::BLUE));
+
+        let colors = frame.to_zone_colors(&map, &[Color::BLACK; 2]);
+        assert!(is_mostly(Some(colors[0]), Color::RED));
+        assert!(is_mostly(Some(colors[1]), Color::BLUE));
+
+        let solid = RgbaImage::from_pixel(5, 5, RED);
+        let frame = image_to_frame(&DynamicImage::ImageRgba8(solid), &map);
+        assert_eq!(frame.get(1, 0), Some(Color::RED));
+    }
+
+    #[test]
+    fn leaves_transparent_cells_unpainted() {
+        let map = OpenRGBMatrixMap {
+            height: 1,
+            width: 2,
+            map: vec![0, 1],
+        };
+        let image = RgbaImage::from_fn(2, 1, |x, _| if x == 0 { RED } else { Rgba([0; 4]) });
+
+        let frame = image_to_frame(&DynamicImage::ImageRgba8(image), &map);
+        assert_eq!(frame.get(0, 0), Some(Color::RED));
+        assert_eq!(frame.get(1, 0), None);
+    }
+
+    #[test]
+    fn decodes_gif_frames_with_their_delays() {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder
+                .encode_frames(vec![
+                    Frame::from_parts(
+                        RgbaImage::from_pixel(2, 2, RED),
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(250, 1),
+                    ),
+                    // Frames without a delay are shown for the default delay.
+                    Frame::from_parts(
+                        RgbaImage::from_pixel(2, 2, BLUE),
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(0, 1),
+                    ),
+                ])
+                .unwrap();
+        }
+
+        let animation = Animation::from_gif(gif.as_slice()).unwrap();
+        assert_eq!(animation.len(), 2);
+        assert_eq!(animation.duration(), Duration::from_millis(350));
+
+        let map = OpenRGBMatrixMap {
+            height: 1,
+            width: 1,
+            map: vec![0],
+        };
+        let frames = animation.frames_for(&map);
+        assert_eq!(frames[0].1, Duration::from_millis(250));
+        assert_eq!(frames[1].1, DEFAULT_FRAME_DELAY);
+        assert!(is_mostly(frames[0].0.get(0, 0), Color::RED));
+        assert!(is_mostly(frames[1].0.get(0, 0), Color::BLUE));
+    }
+}
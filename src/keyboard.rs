@@ -0,0 +1,493 @@
+use crate::{Color, OpenRGBClient, OpenRGBDevice, OpenRGBError, OpenRGBResult};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// Common keyboard keys, independent of how a particular keyboard names its leds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    PrintScreen,
+    ScrollLock,
+    PauseBreak,
+    Backtick,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Minus,
+    Equals,
+    Backspace,
+    Tab,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    LeftBracket,
+    RightBracket,
+    Backslash,
+    CapsLock,
+    Semicolon,
+    Quote,
+    Enter,
+    Pound,
+    LeftShift,
+    IsoBackslash,
+    Comma,
+    Period,
+    Slash,
+    RightShift,
+    LeftControl,
+    LeftWindows,
+    LeftAlt,
+    Space,
+    RightAlt,
+    RightWindows,
+    RightFn,
+    Menu,
+    RightControl,
+    Insert,
+    Home,
+    PageUp,
+    Delete,
+    End,
+    PageDown,
+    Up,
+    Left,
+    Down,
+    Right,
+    NumLock,
+    NumpadDivide,
+    NumpadMultiply,
+    NumpadMinus,
+    NumpadPlus,
+    NumpadEnter,
+    NumpadPeriod,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    MediaPlayPause,
+    MediaStop,
+    MediaPrevious,
+    MediaNext,
+    MediaMute,
+}
+
+/// Every key with the name OpenRGB gives its led and any aliases it can be parsed from.
+const KEY_NAMES: &[(Key, &str, &[&str])] = &[
+    (Key::Escape, "Escape", &["esc"]),
+    (Key::F1, "F1", &[]),
+    (Key::F2, "F2", &[]),
+    (Key::F3, "F3", &[]),
+    (Key::F4, "F4", &[]),
+    (Key::F5, "F5", &[]),
+    (Key::F6, "F6", &[]),
+    (Key::F7, "F7", &[]),
+    (Key::F8, "F8", &[]),
+    (Key::F9, "F9", &[]),
+    (Key::F10, "F10", &[]),
+    (Key::F11, "F11", &[]),
+    (Key::F12, "F12", &[]),
+    (Key::PrintScreen, "Print Screen", &["prtsc", "print"]),
+    (Key::ScrollLock, "Scroll Lock", &[]),
+    (Key::PauseBreak, "Pause/Break", &["pause", "break"]),
+    (Key::Backtick, "`", &["grave", "tilde"]),
+    (Key::Digit0, "0", &[]),
+    (Key::Digit1, "1", &[]),
+    (Key::Digit2, "2", &[]),
+    (Key::Digit3, "3", &[]),
+    (Key::Digit4, "4", &[]),
+    (Key::Digit5, "5", &[]),
+    (Key::Digit6, "6", &[]),
+    (Key::Digit7, "7", &[]),
+    (Key::Digit8, "8", &[]),
+    (Key::Digit9, "9", &[]),
+    (Key::Minus, "-", &[]),
+    (Key::Equals, "=", &[]),
+    (Key::Backspace, "Backspace", &[]),
+    (Key::Tab, "Tab", &[]),
+    (Key::A, "A", &[]),
+    (Key::B, "B", &[]),
+    (Key::C, "C", &[]),
+    (Key::D, "D", &[]),
+    (Key::E, "E", &[]),
+    (Key::F, "F", &[]),
+    (Key::G, "G", &[]),
+    (Key::H, "H", &[]),
+    (Key::I, "I", &[]),
+    (Key::J, "J", &[]),
+    (Key::K, "K", &[]),
+    (Key::L, "L", &[]),
+    (Key::M, "M", &[]),
+    (Key::N, "N", &[]),
+    (Key::O, "O", &[]),
+    (Key::P, "P", &[]),
+    (Key::Q, "Q", &[]),
+    (Key::R, "R", &[]),
+    (Key::S, "S", &[]),
+    (Key::T, "T", &[]),
+    (Key::U, "U", &[]),
+    (Key::V, "V", &[]),
+    (Key::W, "W", &[]),
+    (Key::X, "X", &[]),
+    (Key::Y, "Y", &[]),
+    (Key::Z, "Z", &[]),
+    (Key::LeftBracket, "[", &[]),
+    (Key::RightBracket, "]", &[]),
+    (Key::Backslash, "\\", &[]),
+    (Key::CapsLock, "Caps Lock", &[]),
+    (Key::Semicolon, ";", &[]),
+    (Key::Quote, "'", &[]),
+    (Key::Enter, "Enter", &["return"]),
+    (Key::Pound, "#", &["# (iso)"]),
+    (Key::LeftShift, "Left Shift", &["shift"]),
+    (Key::IsoBackslash, "\\ (ISO)", &[]),
+    (Key::Comma, ",", &[]),
+    (Key::Period, ".", &[]),
+    (Key::Slash, "/", &[]),
+    (Key::RightShift, "Right Shift", &[]),
+    (
+        Key::LeftControl,
+        "Left Control",
+        &["ctrl", "control", "left ctrl"],
+    ),
+    (
+        Key::LeftWindows,
+        "Left Windows",
+        &["windows", "win", "super", "left super"],
+    ),
+    (Key::LeftAlt, "Left Alt", &["alt"]),
+    (Key::Space, "Space", &["spacebar"]),
+    (Key::RightAlt, "Right Alt", &["alt gr", "altgr"]),
+    (Key::RightWindows, "Right Windows", &["right super"]),
+    (Key::RightFn, "Right Fn", &["fn"]),
+    (Key::Menu, "Menu", &[]),
+    (Key::RightControl, "Right Control", &["right ctrl"]),
+    (Key::Insert, "Insert", &["ins"]),
+    (Key::Home, "Home", &[]),
+    (Key::PageUp, "Page Up", &["pgup"]),
+    (Key::Delete, "Delete", &["del"]),
+    (Key::End, "End", &[]),
+    (Key::PageDown, "Page Down", &["pgdn"]),
+    (Key::Up, "Up Arrow", &["up"]),
+    (Key::Left, "Left Arrow", &["left"]),
+    (Key::Down, "Down Arrow", &["down"]),
+    (Key::Right, "Right Arrow", &["right"]),
+    (Key::NumLock, "Num Lock", &[]),
+    (Key::NumpadDivide, "Number Pad /", &[]),
+    (Key::NumpadMultiply, "Number Pad *", &[]),
+    (Key::NumpadMinus, "Number Pad -", &[]),
+    (Key::NumpadPlus, "Number Pad +", &[]),
+    (Key::NumpadEnter, "Number Pad Enter", &[]),
+    (Key::NumpadPeriod, "Number Pad .", &[]),
+    (Key::Numpad0, "Number Pad 0", &[]),
+    (Key::Numpad1, "Number Pad 1", &[]),
+    (Key::Numpad2, "Number Pad 2", &[]),
+    (Key::Numpad3, "Number Pad 3", &[]),
+    (Key::Numpad4, "Number Pad 4", &[]),
+    (Key::Numpad5, "Number Pad 5", &[]),
+    (Key::Numpad6, "Number Pad 6", &[]),
+    (Key::Numpad7, "Number Pad 7", &[]),
+    (Key::Numpad8, "Number Pad 8", &[]),
+    (Key::Numpad9, "Number Pad 9", &[]),
+    (Key::MediaPlayPause, "Media Play/Pause", &[]),
+    (Key::MediaStop, "Media Stop", &[]),
+    (Key::MediaPrevious, "Media Previous", &[]),
+    (Key::MediaNext, "Media Next", &[]),
+    (Key::MediaMute, "Media Mute", &[]),
+];
+
+impl Key {
+    /// Gets the name OpenRGB uses for the key's led, without the `Key: ` prefix.
+    pub fn name(&self) -> &'static str {
+        KEY_NAMES
+            .iter()
+            .find(|(key, _, _)| key == self)
+            .map(|(_, name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    /// Parses a key from a led name such as `Key: Escape`, a bare key name like `Page Up` or an
+    /// alias like `esc`, ignoring case.
+    pub fn from_led_name(name: &str) -> Option<Self> {
+        let name = normalize_key_name(name);
+
+        KEY_NAMES
+            .iter()
+            .find(|(_, key_name, aliases)| {
+                normalize_key_name(key_name) == name || aliases.contains(&name.as_str())
+            })
+            .map(|(key, _, _)| *key)
+    }
+
+    pub fn all() -> impl Iterator<Item = Key> {
+        KEY_NAMES.iter().map(|(key, _, _)| *key)
+    }
+}
+
+impl FromStr for Key {
+    type Err = OpenRGBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_led_name(s).ok_or_else(|| OpenRGBError::InvalidKey(s.to_string()))
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Lowercases the name, strips the `Key: ` prefix OpenRGB gives keyboard leds and the
+/// `(ANSI)` suffix some keys have to tell them apart from their ISO counterparts.
+fn normalize_key_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let name = name.strip_prefix("key:").unwrap_or(&name);
+    let name = name.trim().strip_suffix("(ansi)").unwrap_or(name);
+
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Maps keys to the leds of a keyboard.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+/// let layout = KeyboardLayout::new(&client.get_device(0).await?);
+/// client
+///     .update_keys(0, &layout, &[Key::W, Key::A, Key::S, Key::D], Color::RED)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardLayout {
+    keys: HashMap<Key, usize>,
+    names: HashMap<String, usize>,
+}
+
+impl KeyboardLayout {
+    pub fn new(device: &OpenRGBDevice) -> Self {
+        let mut layout = Self::default();
+
+        for (led_id, led) in device.leds.iter().enumerate() {
+            // Some keyboards have more than one led per key, the first one is used.
+            if let Some(key) = Key::from_led_name(&led.name) {
+                layout.keys.entry(key).or_insert(led_id);
+            }
+
+            layout
+                .names
+                .entry(normalize_key_name(&led.name))
+                .or_insert(led_id);
+        }
+
+        layout
+    }
+
+    /// Gets the id of the key's led.
+    pub fn led(&self, key: Key) -> Option<usize> {
+        self.keys.get(&key).copied()
+    }
+
+    /// Gets the id of a led by its name, for keys that aren't part of [`Key`].
+    pub fn led_by_name(&self, name: &str) -> Option<usize> {
+        self.names.get(&normalize_key_name(name)).copied()
+    }
+
+    /// Gets the ids of the leds of the keys, skipping keys the keyboard doesn't have.
+    pub fn leds(&self, keys: &[Key]) -> Vec<usize> {
+        keys.iter().filter_map(|key| self.led(*key)).collect()
+    }
+
+    /// Iterates over all keys the keyboard has along with the id of their led.
+    pub fn keys(&self) -> impl Iterator<Item = (Key, usize)> + '_ {
+        self.keys.iter().map(|(key, led_id)| (*key, *led_id))
+    }
+}
+
+impl OpenRGBClient {
+    /// Sets the leds of the keys to the color, skipping keys the keyboard doesn't have.
+    ///
+    /// All keys are sent in a single update, the other leds keep the colors last sent to them.
+    pub async fn update_keys(
+        &mut self,
+        device_id: usize,
+        layout: &KeyboardLayout,
+        keys: &[Key],
+        color: Color,
+    ) -> OpenRGBResult<()> {
+        let mut colors = self
+            .colors(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
+            .to_vec();
+
+        for led_id in layout.leds(keys) {
+            if let Some(led) = colors.get_mut(led_id) {
+                *led = color;
+            }
+        }
+
+        self.update_leds(device_id, &colors).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::FakeServer, command::Command, types::tests::device, OpenRGBLed};
+
+    fn keyboard(names: &[&str]) -> OpenRGBDevice {
+        let mut keyboard = device("Keyboard", "1");
+        keyboard.leds = names
+            .iter()
+            .enumerate()
+            .map(|(value, name)| OpenRGBLed {
+                name: name.to_string(),
+                value: value as u32,
+            })
+            .collect();
+        keyboard
+    }
+
+    #[test]
+    fn normalizes_key_names() {
+        let cases = [
+            ("Key: Escape", "escape"),
+            ("key:escape", "escape"),
+            ("  Key:   Page   Up ", "page up"),
+            ("Key: Enter (ANSI)", "enter"),
+            ("Key: \\ (ISO)", "\\ (iso)"),
+            ("Logo", "logo"),
+        ];
+
+        for (name, expected) in &cases {
+            assert_eq!(normalize_key_name(name), *expected, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn parses_keys_from_led_names_and_aliases() {
+        let cases = [
+            ("Key: Escape", Some(Key::Escape)),
+            ("KEY: ESCAPE", Some(Key::Escape)),
+            ("Escape", Some(Key::Escape)),
+            ("esc", Some(Key::Escape)),
+            ("Key: Enter (ANSI)", Some(Key::Enter)),
+            ("Key: \\", Some(Key::Backslash)),
+            ("Key: \\ (ISO)", Some(Key::IsoBackslash)),
+            ("Key: Number Pad 0", Some(Key::Numpad0)),
+            ("ctrl", Some(Key::LeftControl)),
+            ("Key: Escape Hatch", None),
+            ("", None),
+        ];
+
+        for (name, expected) in &cases {
+            assert_eq!(Key::from_led_name(name), *expected, "{:?}", name);
+        }
+
+        assert!(matches!(
+            "nope".parse::<Key>(),
+            Err(OpenRGBError::InvalidKey(name)) if name == "nope"
+        ));
+    }
+
+    #[test]
+    fn every_key_parses_from_its_name() {
+        for key in Key::all() {
+            assert_eq!(Key::from_led_name(&key.to_string()), Some(key));
+            assert_eq!(Key::from_led_name(&format!("Key: {}", key)), Some(key));
+        }
+    }
+
+    #[test]
+    fn maps_keys_to_their_first_led() {
+        let layout = KeyboardLayout::new(&keyboard(&[
+            "Key: Escape",
+            "Key: A",
+            "Key: A",
+            "Logo",
+            "Key: Enter (ANSI)",
+        ]));
+
+        assert_eq!(layout.led(Key::Escape), Some(0));
+        assert_eq!(layout.led(Key::A), Some(1));
+        assert_eq!(layout.led(Key::Enter), Some(4));
+        assert_eq!(layout.led(Key::B), None);
+        assert_eq!(layout.led_by_name("logo"), Some(3));
+        assert_eq!(layout.leds(&[Key::B, Key::Enter, Key::A]), vec![4, 1]);
+        assert_eq!(layout.keys().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn updates_keys_at_once() {
+        let keyboard = keyboard(&["Key: W", "Key: A", "Key: S", "Key: D", "Logo"]);
+        let layout = KeyboardLayout::new(&keyboard);
+        let colors = keyboard.colors.clone();
+
+        let (mut client, server) = FakeServer::connect(vec![keyboard]).await;
+        client.get_device(0).await.unwrap();
+        server.take_requests(&mut client).await;
+
+        let keys = [Key::W, Key::S, Key::Escape];
+        client
+            .update_keys(0, &layout, &keys, Color::WHITE)
+            .await
+            .unwrap();
+
+        let requests = server.take_requests(&mut client).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].command, Command::UpdateLeds as u32);
+        assert_eq!(
+            requests[0].colors(),
+            vec![Color::WHITE, colors[1], Color::WHITE, colors[3], colors[4]]
+        );
+    }
+}
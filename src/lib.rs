@@ -1,11 +1,63 @@
+#[cfg(feature = "server")]
+mod aggregator;
+mod ambient;
+#[cfg(feature = "audio")]
+mod audio;
 mod client;
+mod color;
 mod command;
+mod diff;
+pub mod effects;
 mod error;
+#[cfg(feature = "http")]
+mod http;
+mod identity;
+#[cfg(feature = "image")]
+mod imaging;
 mod io;
+mod keyboard;
+mod matrix;
+#[cfg(feature = "mqtt")]
+mod mqtt;
 mod network;
+mod profile;
+#[cfg(feature = "server")]
+mod proxy;
+mod recording;
+mod scene;
+mod scheduler;
+#[cfg(feature = "server")]
+mod server;
+mod text;
+mod transform;
 mod types;
 
+#[cfg(feature = "server")]
+pub use aggregator::*;
+pub use ambient::*;
+#[cfg(feature = "audio")]
+pub use audio::*;
 pub use client::*;
+pub use color::*;
 pub use error::*;
+#[cfg(feature = "http")]
+pub use http::*;
+pub use identity::*;
+#[cfg(feature = "image")]
+pub use imaging::*;
+pub use keyboard::*;
+pub use matrix::*;
+#[cfg(feature = "mqtt")]
+pub use mqtt::*;
 pub use network::*;
+pub use profile::*;
+#[cfg(feature = "server")]
+pub use proxy::*;
+pub use recording::*;
+pub use scene::*;
+pub use scheduler::*;
+#[cfg(feature = "server")]
+pub use server::*;
+pub use text::*;
+pub use transform::*;
 pub use types::*;
@@ -0,0 +1,207 @@
+use crate::{Color, OpenRGBMatrixMap};
+
+/// Index of a led within its zone.
+pub type LedId = usize;
+
+/// The value OpenRGB uses for matrix cells that have no led.
+pub const NO_LED: u32 = 0xFFFF_FFFF;
+
+impl OpenRGBMatrixMap {
+    /// Gets the led at the cell, `None` if the cell is out of bounds or has no led.
+    pub fn get(&self, x: u32, y: u32) -> Option<LedId> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let cell = (y as usize)
+            .checked_mul(self.width as usize)?
+            .checked_add(x as usize)?;
+        match self.map.get(cell) {
+            Some(&NO_LED) | None => None,
+            Some(&led_id) => Some(led_id as LedId),
+        }
+    }
+
+    /// Iterates over all cells that have a led as `(x, y, led_id)`, row by row.
+    pub fn leds(&self) -> impl Iterator<Item = (u32, u32, LedId)> + '_ {
+        let width = self.width.max(1);
+
+        self.map
+            .iter()
+            .enumerate()
+            .filter(|(_, &led_id)| led_id != NO_LED)
+            .map(move |(cell, &led_id)| {
+                let cell = cell as u32;
+                (cell % width, cell / width, led_id as LedId)
+            })
+    }
+}
+
+/// An image with the dimensions of a matrix zone that can be painted by `(x, y)` and sent with
+/// [`crate::OpenRGBClient::update_matrix`].
+///
+/// Cells that haven't been painted keep the color their led currently has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixFrame {
+    width: u32,
+    height: u32,
+    pixels: Vec<Option<Color>>,
+}
+
+impl MatrixFrame {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![None; width as usize * height as usize],
+        }
+    }
+
+    /// Creates an empty frame with the dimensions of the matrix.
+    pub fn for_map(map: &OpenRGBMatrixMap) -> Self {
+        Self::new(map.width, map.height)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Color> {
+        self.index(x, y).and_then(|index| self.pixels[index])
+    }
+
+    /// Paints the cell, painting outside of the frame does nothing.
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = Some(color);
+        }
+    }
+
+    /// Resets the cell so its led keeps the color it currently has.
+    pub fn unset(&mut self, x: u32, y: u32) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = None;
+        }
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        self.pixels
+            .iter_mut()
+            .for_each(|pixel| *pixel = Some(color));
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel = None);
+    }
+
+    /// Translates the frame into the colors of the zone's leds.
+    ///
+    /// `current` holds the zone's current colors, which are kept for leds whose cell hasn't
+    /// been painted. The result always has the length of `current`.
+    pub fn to_zone_colors(&self, map: &OpenRGBMatrixMap, current: &[Color]) -> Vec<Color> {
+        let mut colors = current.to_vec();
+
+        for (x, y, led_id) in map.leds() {
+            if let (Some(color), Some(led)) = (self.get(x, y), colors.get_mut(led_id)) {
+                *led = color;
+            }
+        }
+
+        colors
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        (y as usize)
+            .checked_mul(self.width as usize)?
+            .checked_add(x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x2 matrix with a hole in each row.
+    fn map() -> OpenRGBMatrixMap {
+        OpenRGBMatrixMap {
+            height: 2,
+            width: 3,
+            map: vec![0, NO_LED, 1, 2, 3, NO_LED],
+        }
+    }
+
+    #[test]
+    fn finds_leds_by_cell() {
+        let map = map();
+
+        assert_eq!(map.get(0, 0), Some(0));
+        assert_eq!(map.get(2, 0), Some(1));
+        assert_eq!(map.get(1, 1), Some(3));
+        assert_eq!(map.get(1, 0), None);
+        assert_eq!(map.get(2, 1), None);
+        assert_eq!(map.get(3, 0), None);
+        assert_eq!(map.get(0, 2), None);
+        assert_eq!(map.get(u32::MAX, u32::MAX), None);
+    }
+
+    #[test]
+    fn doesnt_overflow_on_huge_maps() {
+        let map = OpenRGBMatrixMap {
+            height: u32::MAX,
+            width: u32::MAX,
+            map: vec![0],
+        };
+
+        assert_eq!(map.get(0, 0), Some(0));
+        assert_eq!(map.get(u32::MAX - 1, u32::MAX - 1), None);
+    }
+
+    #[test]
+    fn lists_leds_row_by_row() {
+        let leds: Vec<_> = map().leds().collect();
+        assert_eq!(leds, vec![(0, 0, 0), (2, 0, 1), (0, 1, 2), (1, 1, 3)]);
+    }
+
+    #[test]
+    fn paints_only_inside_the_frame() {
+        let mut frame = MatrixFrame::for_map(&map());
+        frame.set(1, 1, Color::RED);
+        frame.set(3, 0, Color::GREEN);
+        frame.set(0, u32::MAX, Color::GREEN);
+
+        assert_eq!(frame.get(1, 1), Some(Color::RED));
+        assert_eq!(frame.get(0, 0), None);
+        assert_eq!(frame.get(3, 0), None);
+
+        frame.unset(1, 1);
+        assert_eq!(frame.get(1, 1), None);
+    }
+
+    #[test]
+    fn keeps_unpainted_leds() {
+        let mut frame = MatrixFrame::for_map(&map());
+        frame.fill(Color::BLUE);
+        frame.unset(0, 0);
+        frame.set(1, 0, Color::RED);
+
+        let current = [Color::WHITE; 4];
+        assert_eq!(
+            frame.to_zone_colors(&map(), &current),
+            vec![Color::WHITE, Color::BLUE, Color::BLUE, Color::BLUE]
+        );
+
+        // Leds the map points past the zone's colors are ignored.
+        assert_eq!(
+            frame.to_zone_colors(&map(), &current[..2]),
+            vec![Color::WHITE, Color::BLUE]
+        );
+    }
+}
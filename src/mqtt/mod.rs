@@ -0,0 +1,530 @@
+mod packet;
+
+use crate::{
+    Color, DeviceKey, OpenRGBClient, OpenRGBDevice, OpenRGBError, OpenRGBResult,
+    COLOR_MODE_PER_LED, COLOR_MODE_SPECIFIC,
+};
+use packet::{Connect, MqttPacket, Will};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, convert::TryFrom, time::Duration};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{tcp::OwnedWriteHalf, TcpStream},
+    sync::mpsc,
+};
+
+/// Configures an [`MqttBridge`].
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// How long the broker waits for a ping before giving up on the bridge, which pings twice as
+    /// often. Anything under a second turns pings off.
+    pub keep_alive: Duration,
+    /// How often OpenRGB is asked whether its devices changed, publishing them again if so.
+    pub refresh_interval: Duration,
+    /// The prefix Home Assistant listens for discovery messages on.
+    pub discovery_prefix: String,
+    /// The prefix of the state, command and availability topics.
+    pub base_topic: String,
+}
+
+impl MqttConfig {
+    pub fn new<S: Into<String>>(host: S, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            client_id: "openrgb".to_string(),
+            username: None,
+            password: None,
+            keep_alive: Duration::from_secs(30),
+            refresh_interval: Duration::from_secs(10),
+            discovery_prefix: "homeassistant".to_string(),
+            base_topic: "openrgb".to_string(),
+        }
+    }
+
+    pub fn with_client_id<S: Into<String>>(mut self, client_id: S) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    pub fn with_credentials<S: Into<String>>(mut self, username: S, password: Option<S>) -> Self {
+        self.username = Some(username.into());
+        self.password = password.map(Into::into);
+        self
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    pub fn with_discovery_prefix<S: Into<String>>(mut self, discovery_prefix: S) -> Self {
+        self.discovery_prefix = discovery_prefix.into();
+        self
+    }
+
+    pub fn with_base_topic<S: Into<String>>(mut self, base_topic: S) -> Self {
+        self.base_topic = base_topic.into();
+        self
+    }
+
+    fn availability_topic(&self) -> String {
+        format!("{}/status", self.base_topic)
+    }
+}
+
+/// What Home Assistant sees of a device, kept by the bridge since OpenRGB has no notion of a
+/// light being off or its brightness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightState {
+    pub on: bool,
+    pub brightness: u8,
+    pub color: Color,
+    /// The name of the device's mode, `None` if it didn't report a valid active mode.
+    pub effect: Option<String>,
+}
+
+impl LightState {
+    fn from_device(device: &OpenRGBDevice) -> Self {
+        let mode = usize::try_from(device.active_mode)
+            .ok()
+            .and_then(|mode_id| device.modes.get(mode_id));
+        let color = match mode {
+            Some(mode) if mode.color_mode == COLOR_MODE_SPECIFIC && !mode.colors.is_empty() => {
+                mode.colors[0]
+            }
+            _ => device.colors.first().copied().unwrap_or_default(),
+        };
+
+        Self {
+            on: true,
+            brightness: 255,
+            color,
+            effect: mode.map(|mode| mode.name.clone()),
+        }
+    }
+
+    /// Applies a command from Home Assistant's JSON schema, ignoring anything it doesn't
+    /// understand.
+    pub fn update(&mut self, command: &Value) {
+        match command.get("state").and_then(Value::as_str) {
+            Some("ON") => self.on = true,
+            Some("OFF") => self.on = false,
+            _ => {}
+        }
+
+        if let Some(brightness) = command.get("brightness").and_then(Value::as_u64) {
+            self.brightness = brightness.min(255) as u8;
+        }
+
+        if let Some(color) = command.get("color") {
+            let channel = |name: &str| color.get(name).and_then(Value::as_u64);
+            if let (Some(r), Some(g), Some(b)) = (channel("r"), channel("g"), channel("b")) {
+                self.color = Color::new(r.min(255) as u8, g.min(255) as u8, b.min(255) as u8);
+            }
+        }
+
+        if let Some(effect) = command.get("effect").and_then(Value::as_str) {
+            self.effect = Some(effect.to_string());
+        }
+    }
+
+    /// The state in Home Assistant's JSON schema.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "state": if self.on { "ON" } else { "OFF" },
+            "brightness": self.brightness,
+            "color_mode": "rgb",
+            "color": { "r": self.color.r, "g": self.color.g, "b": self.color.b },
+            "effect": self.effect,
+        })
+    }
+
+    /// The color shown on the leds, black while off.
+    pub fn output_color(&self) -> Color {
+        if self.on {
+            self.color.scale(self.brightness as f32 / 255.0)
+        } else {
+            Color::default()
+        }
+    }
+}
+
+struct Light {
+    key: DeviceKey,
+    state: LightState,
+}
+
+/// Publishes every device as a Home Assistant JSON light over MQTT and applies the commands
+/// sent to it.
+///
+/// Devices are published under an id derived from their [`DeviceKey`], so they keep their
+/// entity when OpenRGB orders them differently. Modes are published as effects. Brightness and
+/// color are applied with `update_leds` in per led modes and as the mode's colors in modes with
+/// their own colors, turning a light off shows black.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let client = OpenRGBClient::connect("127.0.0.1:6742", "MQTT").await?;
+/// let config = MqttConfig::new("127.0.0.1", 1883).with_credentials("openrgb", Some("secret"));
+/// MqttBridge::new(client, config).run().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MqttBridge {
+    client: OpenRGBClient,
+    config: MqttConfig,
+    lights: BTreeMap<String, Light>,
+}
+
+impl MqttBridge {
+    pub fn new(client: OpenRGBClient, config: MqttConfig) -> Self {
+        Self {
+            client,
+            config,
+            lights: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the state of a published device by its object id.
+    pub fn light(&self, object_id: &str) -> Option<&LightState> {
+        self.lights.get(object_id).map(|light| &light.state)
+    }
+
+    /// Connects to the broker, publishes every device and applies commands until the broker
+    /// or OpenRGB disconnects.
+    pub async fn run(&mut self) -> OpenRGBResult<()> {
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+        let (mut reader, mut writer) = stream.into_split();
+
+        let availability = self.config.availability_topic();
+        let keep_alive = self.config.keep_alive.as_secs().min(u16::MAX as u64) as u16;
+        let connect = Connect {
+            client_id: &self.config.client_id,
+            keep_alive,
+            username: self.config.username.as_deref(),
+            password: self.config.password.as_deref(),
+            will: Some(Will {
+                topic: &availability,
+                payload: b"offline",
+                retain: true,
+            }),
+        };
+        writer.write_all(&connect.encode()).await?;
+
+        match packet::read_packet(&mut reader).await? {
+            MqttPacket::ConnAck { return_code: 0 } => {}
+            MqttPacket::ConnAck { return_code } => {
+                return Err(OpenRGBError::Mqtt(format!(
+                    "broker refused connection with code {}",
+                    return_code
+                )))
+            }
+            _ => return Err(OpenRGBError::Mqtt("expected connack".to_string())),
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let packet = packet::read_packet(&mut reader).await;
+                let failed = packet.is_err();
+                if sender.send(packet).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        self.publish_devices(&mut writer).await?;
+        let filter = format!("{}/+/set", self.config.base_topic);
+        writer.write_all(&packet::subscribe(1, &[&filter])).await?;
+        writer
+            .write_all(&packet::publish(&availability, b"online", true))
+            .await?;
+
+        // A keep alive of zero tells the broker not to expect pings, the interval only exists to
+        // keep the branch below simple.
+        let pinging = keep_alive > 0;
+        let mut ping = tokio::time::interval(Duration::from_secs(keep_alive.max(2) as u64) / 2);
+        let mut refresh =
+            tokio::time::interval(self.config.refresh_interval.max(Duration::from_millis(100)));
+
+        loop {
+            tokio::select! {
+                packet = receiver.recv() => match packet {
+                    Some(Ok(MqttPacket::Publish { topic, payload, packet_id })) => {
+                        if let Some(packet_id) = packet_id {
+                            writer.write_all(&packet::puback(packet_id)).await?;
+                        }
+                        self.handle_command(&mut writer, &topic, &payload).await?;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(error)) => return Err(error),
+                    None => return Ok(()),
+                },
+                _ = ping.tick(), if pinging => writer.write_all(&packet::pingreq()).await?,
+                _ = refresh.tick() => self.refresh_devices(&mut writer).await?,
+            }
+        }
+    }
+
+    /// Publishes the devices again if OpenRGB reported a change or their number changed.
+    async fn refresh_devices(&mut self, writer: &mut OwnedWriteHalf) -> OpenRGBResult<()> {
+        let count = match self.client.get_device_count().await {
+            Ok(count) => count,
+            Err(OpenRGBError::IO(error)) => return Err(error.into()),
+            Err(_) => return Ok(()),
+        };
+
+        if self.client.take_devices_changed() || count != self.lights.len() {
+            self.publish_devices(writer).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Publishes the discovery config and state of every device, removing the lights of devices
+    /// that are gone. Lights of devices that are still there keep their state.
+    async fn publish_devices(&mut self, writer: &mut OwnedWriteHalf) -> OpenRGBResult<()> {
+        let devices = self.client.get_all_devices().await?;
+        let mut previous = std::mem::take(&mut self.lights);
+
+        for device in &devices {
+            let key = DeviceKey::from(device);
+            let object_id = self.unique_object_id(&key);
+            let state = match previous.remove(&object_id) {
+                Some(light) if light.key == key => light.state,
+                _ => LightState::from_device(device),
+            };
+
+            let config = self.discovery_config(&object_id, device);
+            let topic = format!(
+                "{}/light/{}/config",
+                self.config.discovery_prefix, object_id
+            );
+            writer
+                .write_all(&packet::publish(
+                    &topic,
+                    config.to_string().as_bytes(),
+                    true,
+                ))
+                .await?;
+
+            self.lights.insert(object_id.clone(), Light { key, state });
+            self.publish_state(writer, &object_id).await?;
+        }
+
+        // An empty retained config removes the light from Home Assistant.
+        for object_id in previous.keys() {
+            let topic = format!(
+                "{}/light/{}/config",
+                self.config.discovery_prefix, object_id
+            );
+            writer
+                .write_all(&packet::publish(&topic, &[], true))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn unique_object_id(&self, key: &DeviceKey) -> String {
+        let object_id = object_id(key);
+        let mut unique = object_id.clone();
+        let mut suffix = 2;
+
+        while self.lights.contains_key(&unique) {
+            unique = format!("{}_{}", object_id, suffix);
+            suffix += 1;
+        }
+
+        unique
+    }
+
+    fn discovery_config(&self, object_id: &str, device: &OpenRGBDevice) -> Value {
+        let base = &self.config.base_topic;
+        let effects: Vec<&str> = device.modes.iter().map(|mode| mode.name.as_str()).collect();
+
+        json!({
+            "name": device.name,
+            "unique_id": format!("{}_{}", base, object_id),
+            "object_id": object_id,
+            "schema": "json",
+            "state_topic": format!("{}/{}/state", base, object_id),
+            "command_topic": format!("{}/{}/set", base, object_id),
+            "availability_topic": self.config.availability_topic(),
+            "brightness": true,
+            "supported_color_modes": ["rgb"],
+            "effect": true,
+            "effect_list": effects,
+            "device": {
+                "identifiers": [format!("{}_{}", base, object_id)],
+                "name": device.name,
+                "model": device.description,
+                "sw_version": device.version,
+            },
+        })
+    }
+
+    async fn publish_state(
+        &self,
+        writer: &mut OwnedWriteHalf,
+        object_id: &str,
+    ) -> OpenRGBResult<()> {
+        if let Some(light) = self.lights.get(object_id) {
+            let topic = format!("{}/{}/state", self.config.base_topic, object_id);
+            let state = light.state.to_json().to_string();
+            writer
+                .write_all(&packet::publish(&topic, state.as_bytes(), true))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_command(
+        &mut self,
+        writer: &mut OwnedWriteHalf,
+        topic: &str,
+        payload: &[u8],
+    ) -> OpenRGBResult<()> {
+        let object_id = match topic
+            .strip_prefix(self.config.base_topic.as_str())
+            .and_then(|topic| topic.strip_prefix('/'))
+            .and_then(|topic| topic.strip_suffix("/set"))
+        {
+            Some(object_id) => object_id.to_string(),
+            None => return Ok(()),
+        };
+
+        // Malformed commands and unknown lights are ignored rather than ending the bridge.
+        let command: Value = match serde_json::from_slice(payload) {
+            Ok(command) => command,
+            Err(_) => return Ok(()),
+        };
+        let light = match self.lights.get_mut(&object_id) {
+            Some(light) => light,
+            None => return Ok(()),
+        };
+
+        light.state.update(&command);
+        let key = light.key.clone();
+        let state = light.state.clone();
+
+        match self.apply(&key, &state).await {
+            Ok(()) => {}
+            Err(OpenRGBError::IO(error)) => return Err(error.into()),
+            // The device may be gone or the effect unknown, report the state it's in instead.
+            Err(_) => {
+                let device = self.find_device(&key).await?;
+                if let (Some(device), Some(light)) = (device, self.lights.get_mut(&object_id)) {
+                    light.state = LightState {
+                        on: light.state.on,
+                        brightness: light.state.brightness,
+                        ..LightState::from_device(&device)
+                    };
+                }
+            }
+        }
+
+        self.publish_state(writer, &object_id).await
+    }
+
+    /// Requests the device again, `None` if it's gone. Only losing OpenRGB is an error.
+    async fn find_device(&mut self, key: &DeviceKey) -> OpenRGBResult<Option<OpenRGBDevice>> {
+        let device = match self.client.resolve(key).await {
+            Ok(device_id) => self.client.get_device(device_id).await,
+            Err(error) => Err(error),
+        };
+
+        match device {
+            Ok(device) => Ok(Some(device)),
+            Err(OpenRGBError::IO(error)) => Err(error.into()),
+            Err(_) => Ok(None),
+        }
+    }
+
+    async fn apply(&mut self, key: &DeviceKey, state: &LightState) -> OpenRGBResult<()> {
+        let device_id = self.client.resolve(key).await?;
+        let device = self.client.get_device(device_id).await?;
+
+        let per_led = device
+            .modes
+            .iter()
+            .find(|mode| mode.color_mode == COLOR_MODE_PER_LED);
+
+        let mut mode = match &state.effect {
+            Some(effect) => device
+                .modes
+                .iter()
+                .find(|mode| &mode.name == effect)
+                .cloned()
+                .ok_or_else(|| OpenRGBError::InvalidMode(effect.clone()))?,
+            // Without a known mode the colors can still be shown with leds of their own.
+            None => match per_led {
+                Some(per_led) => per_led.clone(),
+                None => return Ok(()),
+            },
+        };
+
+        let color = state.output_color();
+
+        // Turning off a mode that doesn't show our colors needs a mode that does.
+        if !state.on
+            && mode.color_mode != COLOR_MODE_PER_LED
+            && mode.color_mode != COLOR_MODE_SPECIFIC
+        {
+            if let Some(per_led) = per_led {
+                mode = per_led.clone();
+            }
+        }
+
+        if mode.color_mode == COLOR_MODE_SPECIFIC {
+            let count = mode.colors.len().max(mode.colors_min as usize).max(1);
+            mode.colors = vec![color; count];
+        }
+
+        self.client.update_mode(device_id, &mode).await?;
+
+        if mode.color_mode == COLOR_MODE_PER_LED {
+            let colors = vec![color; device.colors.len()];
+            self.client.update_leds(device_id, &colors).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An MQTT and Home Assistant friendly id made from the device's name and serial or location.
+fn object_id(key: &DeviceKey) -> String {
+    let mut id = key.name.clone();
+    if let Some(suffix) = key.serial.as_ref().or(key.location.as_ref()) {
+        id.push('_');
+        id.push_str(suffix);
+    }
+
+    let mut slug = String::with_capacity(id.len());
+    for character in id.chars() {
+        if character.is_ascii_alphanumeric() {
+            slug.push(character.to_ascii_lowercase());
+        } else if !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+
+    match slug.trim_matches('_') {
+        "" => "device".to_string(),
+        slug => slug.to_string(),
+    }
+}
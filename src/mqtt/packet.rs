@@ -0,0 +1,318 @@
+//! The parts of MQTT 3.1.1 the bridge needs: connecting, publishing and subscribing at QoS 0,
+//! and keeping the connection alive.
+
+use crate::{OpenRGBError, OpenRGBResult};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xC0;
+const PINGRESP: u8 = 0xD0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MqttPacket {
+    ConnAck {
+        return_code: u8,
+    },
+    Publish {
+        topic: String,
+        payload: Vec<u8>,
+        /// Only set for QoS 1 and 2, which have to be acknowledged.
+        packet_id: Option<u16>,
+    },
+    SubAck,
+    PingResp,
+    Other(u8),
+}
+
+pub(crate) struct Will<'a> {
+    pub topic: &'a str,
+    pub payload: &'a [u8],
+    pub retain: bool,
+}
+
+pub(crate) struct Connect<'a> {
+    pub client_id: &'a str,
+    pub keep_alive: u16,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    pub will: Option<Will<'a>>,
+}
+
+impl Connect<'_> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut flags = 0x02; // clean session
+        let mut body = Vec::new();
+        write_string(&mut body, "MQTT");
+        body.push(4); // protocol level 3.1.1
+
+        if let Some(will) = &self.will {
+            flags |= 0x04;
+            if will.retain {
+                flags |= 0x20;
+            }
+        }
+        if self.username.is_some() {
+            flags |= 0x80;
+        }
+        if self.password.is_some() {
+            flags |= 0x40;
+        }
+
+        body.push(flags);
+        body.extend_from_slice(&self.keep_alive.to_be_bytes());
+        write_string(&mut body, self.client_id);
+
+        if let Some(will) = &self.will {
+            write_string(&mut body, will.topic);
+            write_bytes(&mut body, will.payload);
+        }
+        if let Some(username) = self.username {
+            write_string(&mut body, username);
+        }
+        if let Some(password) = self.password {
+            write_string(&mut body, password);
+        }
+
+        frame(CONNECT, &body)
+    }
+}
+
+pub(crate) fn publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    write_string(&mut body, topic);
+    body.extend_from_slice(payload);
+
+    frame(PUBLISH | retain as u8, &body)
+}
+
+pub(crate) fn puback(packet_id: u16) -> Vec<u8> {
+    frame(PUBACK, &packet_id.to_be_bytes())
+}
+
+pub(crate) fn subscribe(packet_id: u16, filters: &[&str]) -> Vec<u8> {
+    let mut body = packet_id.to_be_bytes().to_vec();
+    for filter in filters {
+        write_string(&mut body, filter);
+        body.push(0); // QoS 0
+    }
+
+    frame(SUBSCRIBE, &body)
+}
+
+pub(crate) fn pingreq() -> Vec<u8> {
+    frame(PINGREQ, &[])
+}
+
+pub(crate) async fn read_packet<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+) -> OpenRGBResult<MqttPacket> {
+    let header = reader.read_u8().await?;
+
+    let mut length = 0usize;
+    for shift in (0..28).step_by(7) {
+        let byte = reader.read_u8().await?;
+        length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        } else if shift == 21 {
+            return Err(OpenRGBError::Mqtt("malformed remaining length".to_string()));
+        }
+    }
+
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await?;
+
+    let packet = match header & 0xF0 {
+        CONNACK if body.len() >= 2 => MqttPacket::ConnAck {
+            return_code: body[1],
+        },
+        PUBLISH => {
+            let qos = (header >> 1) & 0x03;
+            let malformed = || OpenRGBError::Mqtt("malformed publish".to_string());
+
+            let topic_length = body
+                .get(..2)
+                .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+                .ok_or_else(malformed)?;
+            let topic = body.get(2..2 + topic_length).ok_or_else(malformed)?;
+            let topic = String::from_utf8(topic.to_vec())?;
+
+            let mut offset = 2 + topic_length;
+            let packet_id = if qos > 0 {
+                let id = body.get(offset..offset + 2).ok_or_else(malformed)?;
+                offset += 2;
+                Some(u16::from_be_bytes([id[0], id[1]]))
+            } else {
+                None
+            };
+
+            MqttPacket::Publish {
+                topic,
+                payload: body[offset..].to_vec(),
+                packet_id,
+            }
+        }
+        SUBACK => MqttPacket::SubAck,
+        PINGRESP => MqttPacket::PingResp,
+        other => MqttPacket::Other(other),
+    };
+
+    Ok(packet)
+}
+
+fn frame(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+
+        if length == 0 {
+            break;
+        }
+    }
+
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    write_bytes(buffer, value.as_bytes());
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, value: &[u8]) {
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(data: &[u8]) -> OpenRGBResult<MqttPacket> {
+        read_packet(&mut &data[..]).await
+    }
+
+    #[test]
+    fn encodes_remaining_lengths() {
+        assert_eq!(frame(PINGREQ, &[]), vec![PINGREQ, 0]);
+        assert_eq!(&frame(PUBLISH, &[0; 127])[..2], &[PUBLISH, 0x7F]);
+        assert_eq!(&frame(PUBLISH, &[0; 128])[..3], &[PUBLISH, 0x80, 0x01]);
+        assert_eq!(&frame(PUBLISH, &[0; 16383])[..3], &[PUBLISH, 0xFF, 0x7F]);
+        assert_eq!(
+            &frame(PUBLISH, &[0; 16384])[..4],
+            &[PUBLISH, 0x80, 0x80, 0x01]
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_remaining_lengths() {
+        for length in &[0, 127, 128, 16383, 16384, 2_097_152] {
+            let payload = vec![7u8; *length];
+            let packet = read(&publish("a/b", &payload, false)).await.unwrap();
+            assert_eq!(
+                packet,
+                MqttPacket::Publish {
+                    topic: "a/b".to_string(),
+                    payload,
+                    packet_id: None,
+                }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_lengths() {
+        assert!(matches!(
+            read(&[PUBLISH, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]).await,
+            Err(OpenRGBError::Mqtt(_))
+        ));
+        assert!(matches!(
+            read(&[PUBLISH, 0x80]).await,
+            Err(OpenRGBError::IO(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reads_acknowledgements() {
+        assert_eq!(
+            read(&[CONNACK, 2, 0, 0]).await.unwrap(),
+            MqttPacket::ConnAck { return_code: 0 }
+        );
+        assert_eq!(
+            read(&[CONNACK, 2, 0, 5]).await.unwrap(),
+            MqttPacket::ConnAck { return_code: 5 }
+        );
+        assert_eq!(
+            read(&[SUBACK, 3, 0, 1, 0]).await.unwrap(),
+            MqttPacket::SubAck
+        );
+        assert_eq!(read(&[PINGRESP, 0]).await.unwrap(), MqttPacket::PingResp);
+        assert_eq!(read(&[0xE0, 0]).await.unwrap(), MqttPacket::Other(0xE0));
+    }
+
+    #[tokio::test]
+    async fn reads_publish_packet_ids() {
+        // QoS 1 with packet id 0x1234.
+        let data = [PUBLISH | 0x02, 7, 0, 1, b't', 0x12, 0x34, b'o', b'n'];
+        assert_eq!(
+            read(&data).await.unwrap(),
+            MqttPacket::Publish {
+                topic: "t".to_string(),
+                payload: b"on".to_vec(),
+                packet_id: Some(0x1234),
+            }
+        );
+
+        // The topic claims more bytes than the packet has.
+        assert!(matches!(
+            read(&[PUBLISH, 3, 0, 9, b't']).await,
+            Err(OpenRGBError::Mqtt(_))
+        ));
+        assert!(matches!(
+            read(&[PUBLISH | 0x02, 3, 0, 1, b't']).await,
+            Err(OpenRGBError::Mqtt(_))
+        ));
+    }
+
+    #[test]
+    fn encodes_connect() {
+        let connect = Connect {
+            client_id: "id",
+            keep_alive: 30,
+            username: Some("user"),
+            password: Some("pass"),
+            will: Some(Will {
+                topic: "s",
+                payload: b"off",
+                retain: true,
+            }),
+        };
+
+        let mut body = vec![0, 4, b'M', b'Q', b'T', b'T', 4, 0xE6, 0, 30];
+        body.extend_from_slice(&[0, 2, b'i', b'd', 0, 1, b's', 0, 3, b'o', b'f', b'f']);
+        body.extend_from_slice(&[0, 4, b'u', b's', b'e', b'r', 0, 4, b'p', b'a', b's', b's']);
+        assert_eq!(connect.encode(), frame(CONNECT, &body));
+        assert_eq!(connect.encode()[1] as usize, body.len());
+    }
+
+    #[test]
+    fn encodes_subscribe() {
+        assert_eq!(
+            subscribe(1, &["a/+"]),
+            vec![SUBSCRIBE, 8, 0, 1, 0, 3, b'a', b'/', b'+', 0]
+        );
+        assert_eq!(puback(0x0102), vec![PUBACK, 2, 1, 2]);
+        assert_eq!(publish("t", b"x", true)[0], PUBLISH | 1);
+    }
+}
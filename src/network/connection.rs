@@ -2,13 +2,20 @@ use super::packet::*;
 use crate::{
     command::Command,
     io::{AsyncOpenRGBReadExt, AsyncOpenRGBWriteExt, OpenRGBSendable},
-    OpenRGBResult, OpenRGBError,
+    OpenRGBError, OpenRGBResult,
 };
 use async_trait::async_trait;
 use std::{convert::TryFrom, io::Cursor};
 
 const MAGIC: u32 = 1111970383;
 
+/// The size of every packet's header, which is followed by its payload.
+pub(crate) const HEADER_LENGTH: usize = 16;
+
+/// The largest payload accepted from the other side. Even the descriptions of devices with
+/// thousands of leds stay far below it, anything larger is a broken or hostile peer.
+pub(crate) const MAX_PAYLOAD_LENGTH: u32 = 16 * 1024 * 1024;
+
 #[async_trait]
 pub(crate) trait OpenRGBConnection {
     async fn send_command<W: AsyncOpenRGBWriteExt>(
@@ -16,14 +23,9 @@ pub(crate) trait OpenRGBConnection {
         command: Command,
         device: Option<usize>,
     ) -> OpenRGBResult<()> {
-        let header = PacketHeader {
-            magic: MAGIC,
-            device: device.unwrap_or(0) as u32,
-            command,
-            length: 0,
-        };
+        let header = PacketHeader::new(device.unwrap_or(0) as u32, command as u32, 0);
 
-        header.serialize(writer).await?;
+        writer.write_all(&header.to_bytes()).await?;
         writer.flush().await?;
         Ok(())
     }
@@ -37,96 +39,131 @@ pub(crate) trait OpenRGBConnection {
         let mut buffer = Vec::new();
         packet.serialize(&mut buffer).await?;
 
-        let header = PacketHeader {
-            magic: MAGIC,
-            device: device.unwrap_or(0) as u32,
-            command,
-            length: buffer.len() as u32,
-        };
+        let header = PacketHeader::new(
+            device.unwrap_or(0) as u32,
+            command as u32,
+            buffer.len() as u32,
+        );
 
-        header.serialize(writer).await?;
+        writer.write_all(&header.to_bytes()).await?;
         writer.write_all(&buffer).await?;
         writer.flush().await?;
         Ok(())
     }
 
     async fn read_packet<R: AsyncOpenRGBReadExt>(reader: &mut R) -> OpenRGBResult<OpenRGBPackets> {
-        let header = PacketHeader::deserialize(reader).await?;
+        let mut header = [0u8; HEADER_LENGTH];
+        reader.read_exact(&mut header).await?;
+
+        let header = PacketHeader::parse(&header)?;
+        let command = Command::try_from(header.command)?;
         let mut buffer = vec![0u8; header.length as usize];
         reader.read_exact(&mut buffer).await?;
 
-        if header.length == 0 {
-            return Ok(OpenRGBPackets::Command(header.command));
-        }
-
-        let mut buffer = Cursor::new(buffer);
-
-        let packet = match header.command {
-            Command::SetClientName => {
-                OpenRGBPackets::SetClientName(SetClientNamePacket::deserialize(&mut buffer).await?)
-            }
-            Command::RequestControllerCount => OpenRGBPackets::RequestControllerCount(
-                RequestControllerCountPacket::deserialize(&mut buffer).await?,
-            ),
-            Command::RequestControllerData => OpenRGBPackets::RequestControllerData(
-                RequestControllerDataPacket::deserialize(&mut buffer).await?,
-            ),
-            Command::UpdateLeds => {
-                OpenRGBPackets::UpdateLeds(UpdateLedsPacket::deserialize(&mut buffer).await?)
-            }
-            Command::UpdateZoneLeds => OpenRGBPackets::UpdateZoneLeds(
-                UpdateZoneLedsPacket::deserialize(&mut buffer).await?,
-            ),
-            Command::UpdateSingleLed => OpenRGBPackets::UpdateSingleLed(
-                UpdateSingleLedPacket::deserialize(&mut buffer).await?,
-            ),
-            Command::ResizeZone => {
-                OpenRGBPackets::ResizeZone(ResizeZonePacket::deserialize(&mut buffer).await?)
-            }
-            Command::UpdateMode => {
-                OpenRGBPackets::UpdateMode(UpdateModePacket::deserialize(&mut buffer).await?)
-            }
-            _ => return Err(OpenRGBError::InvalidPacketBody(header.command)),
-        };
-
-        Ok(packet)
+        decode_payload(command, &buffer).await
     }
 }
 
-#[derive(Debug)]
-struct PacketHeader {
-    pub magic: u32,
-    pub device: u32,
-    pub command: Command,
-    pub length: u32,
+/// Reads the little endian `u32` at the offset, the encoding of every header field.
+pub(crate) fn u32_field(data: &[u8], offset: usize) -> Option<u32> {
+    let field = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
 }
 
-#[async_trait]
-impl OpenRGBSendable for PacketHeader {
-    type Output = Self;
-
-    async fn serialize<W: AsyncOpenRGBWriteExt + Send + Unpin>(
-        &self,
-        writer: &mut W,
-    ) -> OpenRGBResult<()> {
-        writer.write_u32_le(self.magic).await?;
-        writer.write_u32_le(self.device).await?;
-        writer.write_u32_le(self.command.clone() as u32).await?;
-        writer.write_u32_le(self.length).await?;
-        Ok(())
+/// Parses the payload of a packet with the command from its header.
+pub(crate) async fn decode_payload(
+    command: Command,
+    payload: &[u8],
+) -> OpenRGBResult<OpenRGBPackets> {
+    if payload.is_empty() {
+        return Ok(OpenRGBPackets::Command(command));
     }
 
-    async fn deserialize<R: AsyncOpenRGBReadExt>(reader: &mut R) -> OpenRGBResult<Self::Output> {
-        let magic = reader.read_u32_le().await?;
-        let device = reader.read_u32_le().await?;
-        let command = Command::try_from(reader.read_u32_le().await?)?;
-        let length = reader.read_u32_le().await?;
+    let mut buffer = Cursor::new(payload);
 
-        Ok(Self {
-            magic,
+    let packet = match command {
+        Command::SetClientName => {
+            OpenRGBPackets::SetClientName(SetClientNamePacket::deserialize(&mut buffer).await?)
+        }
+        Command::RequestControllerCount => OpenRGBPackets::RequestControllerCount(
+            RequestControllerCountPacket::deserialize(&mut buffer).await?,
+        ),
+        Command::RequestControllerData => OpenRGBPackets::RequestControllerData(
+            RequestControllerDataPacket::deserialize(&mut buffer).await?,
+        ),
+        Command::UpdateLeds => {
+            OpenRGBPackets::UpdateLeds(UpdateLedsPacket::deserialize(&mut buffer).await?)
+        }
+        Command::UpdateZoneLeds => {
+            OpenRGBPackets::UpdateZoneLeds(UpdateZoneLedsPacket::deserialize(&mut buffer).await?)
+        }
+        Command::UpdateSingleLed => {
+            OpenRGBPackets::UpdateSingleLed(UpdateSingleLedPacket::deserialize(&mut buffer).await?)
+        }
+        Command::ResizeZone => {
+            OpenRGBPackets::ResizeZone(ResizeZonePacket::deserialize(&mut buffer).await?)
+        }
+        Command::UpdateMode => {
+            OpenRGBPackets::UpdateMode(UpdateModePacket::deserialize(&mut buffer).await?)
+        }
+        Command::RequestProfileList => OpenRGBPackets::RequestProfileList(
+            RequestProfileListPacket::deserialize(&mut buffer).await?,
+        ),
+        Command::RequestSaveProfile => OpenRGBPackets::SaveProfile(
+            ProfilePacket::deserialize_command(command, &mut buffer).await?,
+        ),
+        Command::RequestLoadProfile => OpenRGBPackets::LoadProfile(
+            ProfilePacket::deserialize_command(command, &mut buffer).await?,
+        ),
+        Command::RequestDeleteProfile => OpenRGBPackets::DeleteProfile(
+            ProfilePacket::deserialize_command(command, &mut buffer).await?,
+        ),
+        _ => return Err(OpenRGBError::InvalidPacketBody(command)),
+    };
+
+    Ok(packet)
+}
+
+/// The header in front of every packet, with the command left as a raw id so packets can be
+/// passed on without understanding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PacketHeader {
+    pub device: u32,
+    pub command: u32,
+    pub length: u32,
+}
+
+impl PacketHeader {
+    pub fn new(device: u32, command: u32, length: u32) -> Self {
+        Self {
             device,
             command,
             length,
-        })
+        }
+    }
+
+    /// Parses a header, refusing anything without the magic or announcing a payload larger
+    /// than [`MAX_PAYLOAD_LENGTH`].
+    pub fn parse(header: &[u8; HEADER_LENGTH]) -> OpenRGBResult<Self> {
+        if u32_field(header, 0) != Some(MAGIC) {
+            return Err(OpenRGBError::UnexpectedPacket);
+        }
+
+        let field = |offset: usize| u32_field(header, offset).unwrap_or(0);
+        let length = field(12);
+        if length > MAX_PAYLOAD_LENGTH {
+            return Err(OpenRGBError::PacketTooLarge(length));
+        }
+
+        Ok(Self::new(field(4), field(8), length))
+    }
+
+    pub fn to_bytes(self) -> [u8; HEADER_LENGTH] {
+        let mut header = [0u8; HEADER_LENGTH];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[4..8].copy_from_slice(&self.device.to_le_bytes());
+        header[8..12].copy_from_slice(&self.command.to_le_bytes());
+        header[12..16].copy_from_slice(&self.length.to_le_bytes());
+        header
     }
 }
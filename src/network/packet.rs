@@ -2,12 +2,13 @@ use crate::{
     command::Command,
     io::{AsyncOpenRGBReadExt, AsyncOpenRGBWriteExt, OpenRGBSendable},
     types::OpenRGBDevice,
-    OpenRGBColor, OpenRGBMode, OpenRGBResult,
+    Color, OpenRGBError, OpenRGBMode, OpenRGBResult,
 };
 use async_trait::async_trait;
 use tokio::io::AsyncWriteExt;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OpenRGBPackets {
     SetClientName(SetClientNamePacket),
     RequestControllerCount(RequestControllerCountPacket),
@@ -17,6 +18,10 @@ pub enum OpenRGBPackets {
     UpdateZoneLeds(UpdateZoneLedsPacket),
     UpdateSingleLed(UpdateSingleLedPacket),
     ResizeZone(ResizeZonePacket),
+    RequestProfileList(RequestProfileListPacket),
+    SaveProfile(ProfilePacket),
+    LoadProfile(ProfilePacket),
+    DeleteProfile(ProfilePacket),
     /// A packet that has no data other than it's id
     Command(Command),
 }
@@ -26,6 +31,7 @@ pub trait OpenRGBPacket: Sync + OpenRGBSendable {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetClientNamePacket {
     pub name: String,
 }
@@ -62,6 +68,7 @@ impl OpenRGBPacket for SetClientNamePacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestControllerCountPacket {
     pub count: u32,
 }
@@ -95,6 +102,7 @@ impl OpenRGBPacket for RequestControllerCountPacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestControllerDataPacket {
     pub device: OpenRGBDevice,
 }
@@ -127,6 +135,7 @@ impl OpenRGBPacket for RequestControllerDataPacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateModePacket {
     pub mode_id: usize,
     pub mode: OpenRGBMode,
@@ -170,12 +179,13 @@ impl OpenRGBPacket for UpdateModePacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateLedsPacket {
-    pub colors: Vec<OpenRGBColor>,
+    pub colors: Vec<Color>,
 }
 
 impl UpdateLedsPacket {
-    pub fn new(colors: Vec<OpenRGBColor>) -> Self {
+    pub fn new(colors: Vec<Color>) -> Self {
         Self { colors }
     }
 }
@@ -206,7 +216,7 @@ impl OpenRGBSendable for UpdateLedsPacket {
         let mut colors = Vec::with_capacity(length);
 
         for _ in 0..length {
-            colors.push(OpenRGBColor::deserialize(reader).await?);
+            colors.push(Color::deserialize(reader).await?);
         }
 
         Ok(Self::new(colors))
@@ -220,13 +230,14 @@ impl OpenRGBPacket for UpdateLedsPacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateZoneLedsPacket {
     pub zone_id: usize,
-    pub colors: Vec<OpenRGBColor>,
+    pub colors: Vec<Color>,
 }
 
 impl UpdateZoneLedsPacket {
-    pub fn new(zone_id: usize, colors: Vec<OpenRGBColor>) -> Self {
+    pub fn new(zone_id: usize, colors: Vec<Color>) -> Self {
         Self { zone_id, colors }
     }
 }
@@ -259,7 +270,7 @@ impl OpenRGBSendable for UpdateZoneLedsPacket {
         let mut colors = Vec::with_capacity(length);
 
         for _ in 0..length {
-            colors.push(OpenRGBColor::deserialize(reader).await?);
+            colors.push(Color::deserialize(reader).await?);
         }
 
         Ok(Self::new(zone_id, colors))
@@ -273,13 +284,14 @@ impl OpenRGBPacket for UpdateZoneLedsPacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateSingleLedPacket {
     pub led_id: usize,
-    pub color: OpenRGBColor,
+    pub color: Color,
 }
 
 impl UpdateSingleLedPacket {
-    pub fn new(led_id: usize, color: OpenRGBColor) -> Self {
+    pub fn new(led_id: usize, color: Color) -> Self {
         Self { led_id, color }
     }
 }
@@ -297,7 +309,7 @@ impl OpenRGBSendable for UpdateSingleLedPacket {
     }
     async fn deserialize<R: AsyncOpenRGBReadExt>(reader: &mut R) -> OpenRGBResult<Self::Output> {
         let led_id = reader.read_u32_le().await? as usize;
-        let color = OpenRGBColor::deserialize(reader).await?;
+        let color = Color::deserialize(reader).await?;
         Ok(Self::new(led_id, color))
     }
 }
@@ -309,6 +321,7 @@ impl OpenRGBPacket for UpdateSingleLedPacket {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResizeZonePacket {
     pub zone_id: usize,
     pub new_size: u32,
@@ -343,3 +356,133 @@ impl OpenRGBPacket for ResizeZonePacket {
         Command::ResizeZone
     }
 }
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestProfileListPacket {
+    pub profiles: Vec<String>,
+}
+
+impl RequestProfileListPacket {
+    pub fn new(profiles: Vec<String>) -> Self {
+        Self { profiles }
+    }
+}
+
+#[async_trait]
+impl OpenRGBSendable for RequestProfileListPacket {
+    type Output = Self;
+    async fn serialize<W: AsyncOpenRGBWriteExt + Send + Unpin>(
+        &self,
+        writer: &mut W,
+    ) -> OpenRGBResult<()> {
+        let mut buffer = Vec::new();
+
+        buffer.write_u16_le(self.profiles.len() as u16).await?;
+
+        for profile in &self.profiles {
+            buffer.write_string(profile).await?;
+        }
+
+        writer.write_u32_le(buffer.len() as u32).await?;
+        writer.write_all(&buffer).await?;
+
+        Ok(())
+    }
+    async fn deserialize<R: AsyncOpenRGBReadExt>(reader: &mut R) -> OpenRGBResult<Self::Output> {
+        let _ = reader.read_u32_le().await?;
+        let length = reader.read_u16_le().await? as usize;
+        let mut profiles = Vec::with_capacity(length);
+
+        for _ in 0..length {
+            profiles.push(reader.read_string().await?);
+        }
+
+        Ok(Self::new(profiles))
+    }
+}
+
+impl OpenRGBPacket for RequestProfileListPacket {
+    fn command(&self) -> Command {
+        Command::RequestProfileList
+    }
+}
+
+/// Saves, loads or deletes a profile, which one depends on `command`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfilePacket {
+    pub command: Command,
+    pub name: String,
+}
+
+impl ProfilePacket {
+    pub fn save(name: String) -> Self {
+        Self {
+            command: Command::RequestSaveProfile,
+            name,
+        }
+    }
+
+    pub fn load(name: String) -> Self {
+        Self {
+            command: Command::RequestLoadProfile,
+            name,
+        }
+    }
+
+    pub fn delete(name: String) -> Self {
+        Self {
+            command: Command::RequestDeleteProfile,
+            name,
+        }
+    }
+
+    /// Reads the payload of a profile packet, the command is only sent in the packet's header.
+    pub async fn deserialize_command<R: AsyncOpenRGBReadExt>(
+        command: Command,
+        reader: &mut R,
+    ) -> OpenRGBResult<Self> {
+        match command {
+            Command::RequestSaveProfile
+            | Command::RequestLoadProfile
+            | Command::RequestDeleteProfile => {}
+            command => return Err(OpenRGBError::InvalidPacketBody(command)),
+        }
+
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await?;
+
+        if buffer.last() == Some(&0x00) {
+            buffer.pop();
+        }
+
+        let name = String::from_utf8(buffer)?;
+        Ok(Self { command, name })
+    }
+}
+
+#[async_trait]
+impl OpenRGBSendable for ProfilePacket {
+    type Output = Self;
+    async fn serialize<W: AsyncOpenRGBWriteExt + Send + Unpin>(
+        &self,
+        writer: &mut W,
+    ) -> OpenRGBResult<()> {
+        // Unlike other strings the name isn't length prefixed, only null terminated.
+        writer.write_all(self.name.as_bytes()).await?;
+        writer.write_u8(0x00).await?;
+        Ok(())
+    }
+    /// Always fails as the payload doesn't say which command it belongs to, use
+    /// [`ProfilePacket::deserialize_command`] instead.
+    async fn deserialize<R: AsyncOpenRGBReadExt>(_: &mut R) -> OpenRGBResult<Self::Output> {
+        Err(OpenRGBError::UnexpectedPacket)
+    }
+}
+
+impl OpenRGBPacket for ProfilePacket {
+    fn command(&self) -> Command {
+        self.command.clone()
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    io::OpenRGBSendable, network::connection::u32_field, DeviceKey, OpenRGBDevice, OpenRGBError,
+    OpenRGBResult,
+};
+use std::{io::Cursor, path::Path};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The magic every profile file starts with, null terminated to 16 bytes.
+pub const PROFILE_HEADER: &[u8; 16] = b"OPENRGB_PROFILE\0";
+
+/// The profile version whose device descriptions match the version of the protocol this crate
+/// implements, the only version that can be read and written.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// A profile saved by OpenRGB as a `.orp` file, holding the full description of every device
+/// at the time it was saved.
+///
+/// Only profiles of [`PROFILE_VERSION`] are supported. The layout of the device descriptions
+/// changes with the profile version and this crate only understands the one of the protocol it
+/// implements, so profiles saved by other versions of OpenRGB fail to read with
+/// [`OpenRGBError::UnsupportedProfileVersion`].
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let profile = Profile::open("Gaming.orp").await?;
+///
+/// for device in &profile.devices {
+///     let mode = &device.modes[device.active_mode as usize];
+///     println!("{}: {}", device.name, mode.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    pub devices: Vec<OpenRGBDevice>,
+}
+
+impl Profile {
+    pub fn new(devices: Vec<OpenRGBDevice>) -> Self {
+        Self { devices }
+    }
+
+    /// Reads a profile of [`PROFILE_VERSION`], refusing any other version.
+    pub async fn read<R: AsyncRead + Unpin + Send>(reader: &mut R) -> OpenRGBResult<Self> {
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header).await?;
+
+        if &header != PROFILE_HEADER {
+            return Err(OpenRGBError::InvalidProfile);
+        }
+
+        let version = reader.read_u32_le().await?;
+        if version != PROFILE_VERSION {
+            return Err(OpenRGBError::UnsupportedProfileVersion(version));
+        }
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+
+        let mut devices = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let size = u32_field(&data, offset)
+                .map(|size| size as usize)
+                .filter(|size| *size >= 4 && offset + size <= data.len())
+                .ok_or(OpenRGBError::InvalidProfile)?;
+
+            // Every description is parsed on its own, so anything we don't understand at the
+            // end of one doesn't throw off the next.
+            let mut description = Cursor::new(&data[offset..offset + size]);
+            let device = OpenRGBDevice::deserialize(&mut description)
+                .await
+                .map_err(|error| match error {
+                    OpenRGBError::IO(_) => OpenRGBError::InvalidProfile,
+                    error => error,
+                })?;
+            devices.push(device);
+            offset += size;
+        }
+
+        Ok(Self { devices })
+    }
+
+    /// Writes the profile as [`PROFILE_VERSION`].
+    pub async fn write<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> OpenRGBResult<()> {
+        writer.write_all(PROFILE_HEADER).await?;
+        writer.write_u32_le(PROFILE_VERSION).await?;
+
+        for device in &self.devices {
+            device.serialize(writer).await?;
+        }
+
+        writer.flush().await?;
+        Ok(())
+    }
+
+    pub async fn open<P: AsRef<Path>>(path: P) -> OpenRGBResult<Self> {
+        let data = tokio::fs::read(path).await?;
+        Self::read(&mut data.as_slice()).await
+    }
+
+    pub async fn save<P: AsRef<Path>>(&self, path: P) -> OpenRGBResult<()> {
+        let mut data = Vec::new();
+        self.write(&mut data).await?;
+        tokio::fs::write(path, data).await?;
+        Ok(())
+    }
+
+    /// Finds the saved description of a device, see [`DeviceKey::matches`].
+    pub fn device(&self, key: &DeviceKey) -> Option<&OpenRGBDevice> {
+        self.devices.iter().find(|device| key.matches(device))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::tests::device;
+
+    async fn write(profile: &Profile) -> Vec<u8> {
+        let mut data = Vec::new();
+        profile.write(&mut data).await.unwrap();
+        data
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let profile = Profile::new(vec![device("Strip", "1234"), device("Keyboard", "")]);
+        let data = write(&profile).await;
+        assert_eq!(&data[..16], PROFILE_HEADER);
+
+        let read = Profile::read(&mut data.as_slice()).await.unwrap();
+        assert_eq!(format!("{:?}", read), format!("{:?}", profile));
+        assert_eq!(
+            read.device(&DeviceKey::new("Keyboard")).unwrap().name,
+            "Keyboard"
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_unknown_trailing_data() {
+        let mut data = write(&Profile::new(vec![device("Strip", "1234")])).await;
+
+        // Grows the description as a newer version of a device would.
+        let size = data.len() - 20 + 3;
+        data[20..24].copy_from_slice(&(size as u32).to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3]);
+        data.extend_from_slice(&write(&Profile::new(vec![device("Keyboard", "")])).await[20..]);
+
+        let read = Profile::read(&mut data.as_slice()).await.unwrap();
+        let names: Vec<&str> = read
+            .devices
+            .iter()
+            .map(|device| device.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Strip", "Keyboard"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_profiles() {
+        let data = write(&Profile::new(vec![device("Strip", "1234")])).await;
+
+        let mut header = data.clone();
+        header[0] = b'X';
+        assert!(matches!(
+            Profile::read(&mut header.as_slice()).await,
+            Err(OpenRGBError::InvalidProfile)
+        ));
+
+        let mut version = data.clone();
+        version[16] = 2;
+        assert!(matches!(
+            Profile::read(&mut version.as_slice()).await,
+            Err(OpenRGBError::UnsupportedProfileVersion(2))
+        ));
+
+        // The size covers the whole file, but the description inside it is cut short.
+        let mut truncated = data.clone();
+        truncated.truncate(data.len() - 10);
+        let size = truncated.len() - 20;
+        truncated[20..24].copy_from_slice(&(size as u32).to_le_bytes());
+        assert!(matches!(
+            Profile::read(&mut truncated.as_slice()).await,
+            Err(OpenRGBError::InvalidProfile)
+        ));
+
+        let mut missing = data;
+        missing.truncate(missing.len() - 10);
+        assert!(matches!(
+            Profile::read(&mut missing.as_slice()).await,
+            Err(OpenRGBError::InvalidProfile)
+        ));
+    }
+}
@@ -0,0 +1,483 @@
+use crate::{
+    command::Command, network::connection::u32_field, packet::OpenRGBPackets, Notifier,
+    OpenRGBClient, OpenRGBDevice, OpenRGBResult, OpenRGBServer, RawPacket, ServerHandler,
+    Transport,
+};
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{io::WriteHalf, net::TcpListener, task::JoinHandle};
+
+/// The leds a write changes, a whole device, a single zone or a single led.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LedTarget {
+    pub device_id: u32,
+    /// `None` for writes that can change every zone of the device, or single leds whose zone
+    /// isn't known.
+    pub zone_id: Option<u32>,
+    /// `None` for writes that can change every led of the zone.
+    pub led_id: Option<u32>,
+}
+
+impl LedTarget {
+    pub fn device(device_id: u32) -> Self {
+        Self {
+            device_id,
+            zone_id: None,
+            led_id: None,
+        }
+    }
+
+    pub fn zone(device_id: u32, zone_id: u32) -> Self {
+        Self {
+            device_id,
+            zone_id: Some(zone_id),
+            led_id: None,
+        }
+    }
+
+    /// A single led by its index in the whole device, along with the zone it belongs to if
+    /// known.
+    pub fn led(device_id: u32, zone_id: Option<u32>, led_id: u32) -> Self {
+        Self {
+            device_id,
+            zone_id,
+            led_id: Some(led_id),
+        }
+    }
+
+    /// Returns `true` if both targets share any leds.
+    ///
+    /// A single led whose zone isn't known overlaps every zone of its device.
+    pub fn overlaps(&self, other: &LedTarget) -> bool {
+        self.device_id == other.device_id
+            && (self.zone_id.is_none() || other.zone_id.is_none() || self.zone_id == other.zone_id)
+            && (self.led_id.is_none() || other.led_id.is_none() || self.led_id == other.led_id)
+    }
+
+    /// Finds the leds a packet writes to, `None` for packets that don't change any leds.
+    ///
+    /// Which zone a single led belongs to isn't part of the packet, it's looked up in the sizes
+    /// of the device's zones when given.
+    pub fn of(packet: &RawPacket, zone_sizes: Option<&[u32]>) -> Option<Self> {
+        match Command::try_from(packet.command).ok()? {
+            Command::UpdateLeds | Command::UpdateMode | Command::SetCustomMode => {
+                Some(Self::device(packet.device))
+            }
+            Command::UpdateZoneLeds => {
+                Some(Self::zone(packet.device, u32_field(&packet.payload, 4)?))
+            }
+            Command::UpdateSingleLed => {
+                let led_id = u32_field(&packet.payload, 0)?;
+                let zone_id = zone_sizes.and_then(|sizes| zone_of(sizes, led_id));
+                Some(Self::led(packet.device, zone_id, led_id))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Finds the zone holding the led, zones being laid out one after another.
+fn zone_of(zone_sizes: &[u32], led_id: u32) -> Option<u32> {
+    let mut start = 0u32;
+    for (zone_id, size) in zone_sizes.iter().enumerate() {
+        let end = start.saturating_add(*size);
+        if led_id < end {
+            return Some(zone_id as u32);
+        }
+        start = end;
+    }
+
+    None
+}
+
+/// The sizes of every zone by device id, kept up to date from the device descriptions the
+/// upstream server sends.
+type ZoneLayouts = Arc<Mutex<HashMap<u32, Vec<u32>>>>;
+
+fn zone_sizes(device: &OpenRGBDevice) -> Vec<u32> {
+    device.zones.iter().map(|zone| zone.leds_count).collect()
+}
+
+#[derive(Debug, Clone)]
+struct Claim {
+    client_id: usize,
+    priority: i32,
+    last_write: Instant,
+}
+
+/// Decides which client may write to which leds.
+///
+/// A write claims its leds for the client. Writes overlapping a claim of another client with a
+/// higher priority are refused until that client disconnects or, with a release timeout, stops
+/// writing for that long. Clients with the same priority don't block each other.
+///
+/// Claims past the release timeout are ignored as soon as they expire, whether or not anything
+/// was written since.
+#[derive(Debug, Clone, Default)]
+pub struct Arbiter {
+    claims: HashMap<LedTarget, Claim>,
+    release_after: Option<Duration>,
+}
+
+impl Arbiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Releases claims back to lower priority clients once they haven't been written to for the
+    /// duration.
+    pub fn with_release_timeout(mut self, release_after: Duration) -> Self {
+        self.release_after = Some(release_after);
+        self
+    }
+
+    pub fn release_timeout(&self) -> Option<Duration> {
+        self.release_after
+    }
+
+    /// Returns `true` if the client may write to the target, claiming it if so.
+    pub fn request(&mut self, client_id: usize, priority: i32, target: LedTarget) -> bool {
+        let now = Instant::now();
+        self.expire();
+
+        let blocked = self.claims.iter().any(|(claimed, claim)| {
+            claim.client_id != client_id && claim.priority > priority && claimed.overlaps(&target)
+        });
+
+        if !blocked {
+            self.claims.insert(
+                target,
+                Claim {
+                    client_id,
+                    priority,
+                    last_write: now,
+                },
+            );
+        }
+
+        !blocked
+    }
+
+    /// Drops every claim of the client, usually because it disconnected.
+    pub fn release(&mut self, client_id: usize) {
+        self.claims.retain(|_, claim| claim.client_id != client_id);
+    }
+
+    /// Drops every claim past the release timeout.
+    pub fn expire(&mut self) {
+        let now = Instant::now();
+        let release_after = self.release_after;
+        self.claims
+            .retain(|_, claim| !Self::expired(release_after, claim, now));
+    }
+
+    /// Returns the client currently holding the exact target.
+    pub fn holder(&self, target: &LedTarget) -> Option<usize> {
+        self.claims
+            .get(target)
+            .filter(|claim| !Self::expired(self.release_after, claim, Instant::now()))
+            .map(|claim| claim.client_id)
+    }
+
+    fn expired(release_after: Option<Duration>, claim: &Claim, now: Instant) -> bool {
+        match release_after {
+            Some(release_after) => now.duration_since(claim.last_write) >= release_after,
+            None => false,
+        }
+    }
+}
+
+/// Configures a [`Proxy`].
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// Priorities by client name, as sent with `SetClientName`.
+    pub priorities: HashMap<String, i32>,
+    /// The priority of clients not in `priorities`.
+    pub default_priority: i32,
+    pub release_after: Option<Duration>,
+}
+
+impl ProxyConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_priority<S: Into<String>>(mut self, name: S, priority: i32) -> Self {
+        self.priorities.insert(name.into(), priority);
+        self
+    }
+
+    pub fn with_default_priority(mut self, priority: i32) -> Self {
+        self.default_priority = priority;
+        self
+    }
+
+    pub fn with_release_timeout(mut self, release_after: Duration) -> Self {
+        self.release_after = Some(release_after);
+        self
+    }
+
+    pub fn priority(&self, name: &str) -> i32 {
+        self.priorities
+            .get(name)
+            .copied()
+            .unwrap_or(self.default_priority)
+    }
+}
+
+/// Sits in front of an OpenRGB server, giving every client its own [`OpenRGBClient`] connected
+/// to it and arbitrating led writes between them, see [`Arbiter`].
+///
+/// Everything other than refused led writes is forwarded untouched in both directions.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # use std::time::Duration;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let config = ProxyConfig::new()
+///     .with_priority("Ambient", 10)
+///     .with_release_timeout(Duration::from_secs(5));
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:6743").await?;
+/// Proxy::new("127.0.0.1:6742".parse().unwrap(), config)
+///     .serve(listener)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Proxy {
+    upstream: SocketAddr,
+    config: Arc<ProxyConfig>,
+    arbiter: Arc<Mutex<Arbiter>>,
+    layouts: ZoneLayouts,
+}
+
+impl Proxy {
+    pub fn new(upstream: SocketAddr, config: ProxyConfig) -> Self {
+        let mut arbiter = Arbiter::new();
+        if let Some(release_after) = config.release_after {
+            arbiter = arbiter.with_release_timeout(release_after);
+        }
+
+        Self {
+            upstream,
+            config: Arc::new(config),
+            arbiter: Arc::new(Mutex::new(arbiter)),
+            layouts: Arc::default(),
+        }
+    }
+
+    pub fn arbiter(&self) -> Arc<Mutex<Arbiter>> {
+        self.arbiter.clone()
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> OpenRGBResult<()> {
+        let client_ids = Arc::new(AtomicUsize::new(0));
+
+        OpenRGBServer::new(move |_, notifier| {
+            let client_id = client_ids.fetch_add(1, Ordering::Relaxed);
+            ProxyHandler::connect(
+                self.upstream,
+                client_id,
+                self.config.clone(),
+                self.arbiter.clone(),
+                self.layouts.clone(),
+                notifier,
+            )
+        })
+        .serve(listener)
+        .await
+    }
+}
+
+struct ProxyHandler {
+    client_id: usize,
+    priority: i32,
+    config: Arc<ProxyConfig>,
+    arbiter: Arc<Mutex<Arbiter>>,
+    layouts: ZoneLayouts,
+    upstream: WriteHalf<Box<dyn Transport>>,
+    forwarder: JoinHandle<()>,
+}
+
+impl ProxyHandler {
+    async fn connect(
+        upstream: SocketAddr,
+        client_id: usize,
+        config: Arc<ProxyConfig>,
+        arbiter: Arc<Mutex<Arbiter>>,
+        layouts: ZoneLayouts,
+        notifier: Notifier,
+    ) -> OpenRGBResult<Self> {
+        let mut client = OpenRGBClient::connect(upstream, "OpenRGB Proxy").await?;
+
+        let devices = client.get_all_devices().await?;
+        *layouts.lock().expect("layouts lock poisoned") = devices
+            .iter()
+            .enumerate()
+            .map(|(device_id, device)| (device_id as u32, zone_sizes(device)))
+            .collect();
+
+        let (mut reader, writer) = tokio::io::split(client.into_transport());
+
+        // Everything the server sends goes back to the client as is. The read half keeps the
+        // upstream connection open, so the handler stops the task once the client is gone.
+        let reader_layouts = layouts.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Ok(Some(packet)) = RawPacket::read(&mut reader).await {
+                Self::track_layouts(&reader_layouts, &packet).await;
+
+                if !notifier.send(packet) {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            client_id,
+            priority: config.default_priority,
+            config,
+            arbiter,
+            layouts,
+            upstream: writer,
+            forwarder,
+        })
+    }
+
+    /// Keeps the zone layouts in line with the descriptions the client is sent. Once the device
+    /// list changes they are unknown until the client asks for the devices again.
+    async fn track_layouts(layouts: &ZoneLayouts, packet: &RawPacket) {
+        if packet.command == Command::DeviceListUpdated as u32 {
+            layouts.lock().expect("layouts lock poisoned").clear();
+        } else if packet.command == Command::RequestControllerData as u32 {
+            if let Ok(OpenRGBPackets::RequestControllerData(data)) = packet.decode().await {
+                layouts
+                    .lock()
+                    .expect("layouts lock poisoned")
+                    .insert(packet.device, zone_sizes(&data.device));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ServerHandler for ProxyHandler {
+    async fn handle(&mut self, request: RawPacket) -> OpenRGBResult<Vec<RawPacket>> {
+        if request.command == Command::SetClientName as u32 {
+            let name = String::from_utf8_lossy(&request.payload);
+            self.priority = self.config.priority(name.trim_end_matches('\0'));
+        }
+
+        let target = {
+            let layouts = self.layouts.lock().expect("layouts lock poisoned");
+            LedTarget::of(
+                &request,
+                layouts.get(&request.device).map(|sizes| sizes.as_slice()),
+            )
+        };
+
+        if let Some(target) = target {
+            let allowed = self.arbiter.lock().expect("arbiter lock poisoned").request(
+                self.client_id,
+                self.priority,
+                target,
+            );
+
+            // None of the led writes expect an answer, so refused ones are simply dropped.
+            if !allowed {
+                return Ok(Vec::new());
+            }
+        }
+
+        request.write(&mut self.upstream).await?;
+        Ok(Vec::new())
+    }
+}
+
+impl Drop for ProxyHandler {
+    fn drop(&mut self) {
+        self.forwarder.abort();
+
+        if let Ok(mut arbiter) = self.arbiter.lock() {
+            arbiter.release(self.client_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_overlapping_targets() {
+        let device = LedTarget::device(1);
+        let zone = LedTarget::zone(1, 0);
+        let led = LedTarget::led(1, Some(0), 2);
+
+        assert!(device.overlaps(&zone) && zone.overlaps(&device));
+        assert!(zone.overlaps(&led) && led.overlaps(&zone));
+        assert!(led.overlaps(&LedTarget::led(1, None, 2)));
+
+        assert!(!device.overlaps(&LedTarget::device(2)));
+        assert!(!zone.overlaps(&LedTarget::zone(1, 1)));
+        assert!(!led.overlaps(&LedTarget::led(1, Some(1), 5)));
+        assert!(!led.overlaps(&LedTarget::led(1, Some(0), 3)));
+    }
+
+    #[test]
+    fn finds_the_zone_of_leds() {
+        let sizes = [3, 0, 2];
+
+        assert_eq!(zone_of(&sizes, 0), Some(0));
+        assert_eq!(zone_of(&sizes, 2), Some(0));
+        assert_eq!(zone_of(&sizes, 3), Some(2));
+        assert_eq!(zone_of(&sizes, 4), Some(2));
+        assert_eq!(zone_of(&sizes, 5), None);
+        assert_eq!(zone_of(&[], 0), None);
+    }
+
+    #[test]
+    fn blocks_lower_priority_clients_on_overlapping_claims() {
+        let mut arbiter = Arbiter::new();
+
+        assert!(arbiter.request(1, 10, LedTarget::zone(0, 0)));
+        assert!(!arbiter.request(2, 0, LedTarget::led(0, Some(0), 1)));
+        assert!(!arbiter.request(2, 0, LedTarget::device(0)));
+        assert_eq!(arbiter.holder(&LedTarget::zone(0, 0)), Some(1));
+
+        // Disjoint claims and equal priorities don't block.
+        assert!(arbiter.request(2, 0, LedTarget::zone(0, 1)));
+        assert!(arbiter.request(2, 0, LedTarget::zone(1, 0)));
+        assert!(arbiter.request(3, 10, LedTarget::zone(0, 0)));
+
+        // Higher priorities take over.
+        assert!(arbiter.request(4, 20, LedTarget::device(0)));
+        assert!(!arbiter.request(1, 10, LedTarget::zone(0, 0)));
+
+        arbiter.release(4);
+        assert!(arbiter.request(1, 10, LedTarget::zone(0, 0)));
+        assert!(!arbiter.request(2, 0, LedTarget::zone(0, 0)));
+    }
+
+    #[test]
+    fn releases_expired_claims() {
+        let mut arbiter = Arbiter::new().with_release_timeout(Duration::from_millis(20));
+
+        assert!(arbiter.request(1, 10, LedTarget::device(0)));
+        assert!(!arbiter.request(2, 0, LedTarget::zone(0, 0)));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(arbiter.holder(&LedTarget::device(0)), None);
+        assert!(arbiter.request(2, 0, LedTarget::zone(0, 0)));
+        assert_eq!(arbiter.holder(&LedTarget::zone(0, 0)), Some(2));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::{
+    command::Command,
+    network::{
+        connection::{decode_payload, u32_field, PacketHeader, HEADER_LENGTH, MAX_PAYLOAD_LENGTH},
+        packet::OpenRGBPackets,
+    },
+    OpenRGBError, OpenRGBResult, Transport,
+};
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    pin::Pin,
+    sync::{mpsc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpListener,
+    sync::oneshot,
+};
+
+/// The magic every recording file starts with.
+pub const RECORDING_HEADER: &[u8; 8] = b"ORGBREC1";
+
+/// Which way a recorded packet went, seen from the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketDirection {
+    Sent,
+    Received,
+}
+
+/// A packet as it went over the wire, header included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedPacket {
+    /// Time since the recording started.
+    pub time: Duration,
+    pub direction: PacketDirection,
+    pub data: Vec<u8>,
+}
+
+impl RecordedPacket {
+    /// The device id from the packet's header.
+    pub fn device_id(&self) -> u32 {
+        u32_field(&self.data, 4).unwrap_or(0)
+    }
+
+    /// The raw command id from the packet's header.
+    pub fn command_id(&self) -> u32 {
+        u32_field(&self.data, 8).unwrap_or(0)
+    }
+
+    /// Parses the packet the way the client does.
+    pub async fn decode(&self) -> OpenRGBResult<OpenRGBPackets> {
+        let command = Command::try_from(self.command_id())?;
+        decode_payload(command, self.data.get(HEADER_LENGTH..).unwrap_or(&[])).await
+    }
+}
+
+/// Packets recorded by a [`RecordingTransport`].
+///
+/// Recordings are stored as [`RECORDING_HEADER`] followed by every packet as its time since
+/// the start in microseconds (`u64`), its direction (`u8`, `0` for sent), its length (`u32`) and
+/// its bytes, all little endian.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    pub packets: Vec<RecordedPacket>,
+}
+
+impl Recording {
+    pub fn read<R: Read>(mut reader: R) -> OpenRGBResult<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        if &header != RECORDING_HEADER {
+            return Err(OpenRGBError::InvalidRecording);
+        }
+
+        let mut packets = Vec::new();
+        loop {
+            let mut time = [0u8; 8];
+            match reader.read_exact(&mut time) {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(error) => return Err(error.into()),
+            }
+
+            let mut direction = [0u8; 1];
+            let mut length = [0u8; 4];
+            reader.read_exact(&mut direction)?;
+            reader.read_exact(&mut length)?;
+
+            let length = u32::from_le_bytes(length);
+            if length as usize > HEADER_LENGTH + MAX_PAYLOAD_LENGTH as usize {
+                return Err(OpenRGBError::InvalidRecording);
+            }
+
+            let mut data = vec![0u8; length as usize];
+            reader.read_exact(&mut data)?;
+
+            packets.push(RecordedPacket {
+                time: Duration::from_micros(u64::from_le_bytes(time)),
+                direction: match direction[0] {
+                    0 => PacketDirection::Sent,
+                    1 => PacketDirection::Received,
+                    _ => return Err(OpenRGBError::InvalidRecording),
+                },
+                data,
+            });
+        }
+
+        Ok(Self { packets })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> OpenRGBResult<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the recording along with a description of every packet, decoding them as
+    /// [`OpenRGBPackets`] where possible.
+    pub async fn pretty_print<W: Write>(&self, out: &mut W) -> OpenRGBResult<()> {
+        for packet in &self.packets {
+            let arrow = match packet.direction {
+                PacketDirection::Sent => "->",
+                PacketDirection::Received => "<-",
+            };
+            let command = match Command::try_from(packet.command_id()) {
+                Ok(command) => command.to_string(),
+                Err(_) => format!("unknown command {}", packet.command_id()),
+            };
+
+            writeln!(
+                out,
+                "[{:>10.6}s] {} device {} {} ({} bytes)",
+                packet.time.as_secs_f64(),
+                arrow,
+                packet.device_id(),
+                command,
+                packet.data.len().saturating_sub(HEADER_LENGTH),
+            )?;
+
+            match packet.decode().await {
+                Ok(decoded) => writeln!(out, "{:#?}", decoded)?,
+                Err(error) => writeln!(out, "could not decode packet: {}", error)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Answers every client connecting to the listener exactly like the recorded server did,
+    /// one client at a time.
+    ///
+    /// A client that strays from the recording or disconnects early is dropped, the next one is
+    /// still served.
+    pub async fn serve(&self, listener: TcpListener) -> OpenRGBResult<()> {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let _ = self.replay(&mut stream).await;
+        }
+    }
+
+    /// Plays the server's side of the recording over the connection.
+    ///
+    /// Every recorded packet from the client has to be matched by a packet with the same
+    /// command from the connected client before the server's next packets are sent.
+    pub async fn replay<T: Transport>(&self, transport: &mut T) -> OpenRGBResult<()> {
+        for packet in &self.packets {
+            match packet.direction {
+                PacketDirection::Received => {
+                    transport.write_all(&packet.data).await?;
+                    transport.flush().await?;
+                }
+                PacketDirection::Sent => {
+                    let mut header = [0u8; HEADER_LENGTH];
+                    transport.read_exact(&mut header).await?;
+
+                    let header = PacketHeader::parse(&header)?;
+                    let mut payload = vec![0u8; header.length as usize];
+                    transport.read_exact(&mut payload).await?;
+
+                    if header.command != packet.command_id() {
+                        return Err(OpenRGBError::ReplayMismatch(
+                            packet.command_id(),
+                            header.command,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a stream of bytes into packets using the length in their header.
+#[derive(Debug, Default)]
+struct PacketSplitter {
+    buffer: Vec<u8>,
+}
+
+impl PacketSplitter {
+    fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut packets = Vec::new();
+        while self.buffer.len() >= HEADER_LENGTH {
+            let length = HEADER_LENGTH + u32_field(&self.buffer, 12).unwrap_or(0) as usize;
+            if self.buffer.len() < length {
+                break;
+            }
+
+            packets.push(self.buffer.drain(..length).collect());
+        }
+
+        packets
+    }
+}
+
+/// Splits both directions into packets and hands them to a thread writing them to the sink,
+/// so a slow sink never blocks the connection.
+///
+/// Dropping the recorder leaves the thread to write what is left on its own.
+struct Recorder {
+    start: Instant,
+    sent: PacketSplitter,
+    received: PacketSplitter,
+    entries: Option<mpsc::Sender<Vec<u8>>>,
+    finished: oneshot::Receiver<std::io::Result<()>>,
+}
+
+impl Recorder {
+    fn new<W: Write + Send + 'static>(sink: W) -> Self {
+        let (entries, receiver) = mpsc::channel::<Vec<u8>>();
+        let (done, finished) = oneshot::channel();
+
+        std::thread::spawn(move || {
+            let _ = done.send(Self::write_entries(sink, receiver));
+        });
+
+        Self {
+            start: Instant::now(),
+            sent: PacketSplitter::default(),
+            received: PacketSplitter::default(),
+            entries: Some(entries),
+            finished,
+        }
+    }
+
+    fn write_entries<W: Write>(
+        mut sink: W,
+        receiver: mpsc::Receiver<Vec<u8>>,
+    ) -> std::io::Result<()> {
+        sink.write_all(RECORDING_HEADER)?;
+        sink.flush()?;
+
+        // Flushes whenever the connection goes quiet rather than after every packet.
+        while let Ok(entry) = receiver.recv() {
+            sink.write_all(&entry)?;
+            for entry in receiver.try_iter() {
+                sink.write_all(&entry)?;
+            }
+            sink.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, direction: PacketDirection, data: &[u8]) -> std::io::Result<()> {
+        let splitter = match direction {
+            PacketDirection::Sent => &mut self.sent,
+            PacketDirection::Received => &mut self.received,
+        };
+
+        for packet in splitter.push(data) {
+            let time = self.start.elapsed().as_micros() as u64;
+
+            let mut entry = Vec::with_capacity(13 + packet.len());
+            entry.extend_from_slice(&time.to_le_bytes());
+            entry.push((direction == PacketDirection::Received) as u8);
+            entry.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+            entry.extend_from_slice(&packet);
+
+            let sent = match &self.entries {
+                Some(entries) => entries.send(entry).is_ok(),
+                None => false,
+            };
+            if !sent {
+                // The writer only hangs up after failing, its error may not have arrived yet.
+                self.entries = None;
+                return Err(match self.finished.try_recv() {
+                    Ok(Err(error)) => error,
+                    _ => std::io::ErrorKind::BrokenPipe.into(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits for every recorded packet to be written, returning the error that stopped the
+    /// writer if any.
+    async fn finish(mut self) -> std::io::Result<()> {
+        self.entries = None;
+
+        match self.finished.await {
+            Ok(result) => result,
+            Err(_) => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+}
+
+/// Wraps a connection and records every packet going over it, see [`Recording`].
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example() -> OpenRGBResult<()> {
+/// let stream = tokio::net::TcpStream::connect("127.0.0.1:6742").await?;
+/// let transport = RecordingTransport::create(stream, "session.orgbrec")?;
+/// let mut client = OpenRGBClient::with_transport(transport, "Example").await?;
+/// client.refresh_all().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingTransport<T> {
+    inner: T,
+    recorder: Mutex<Recorder>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Records to the sink, starting with [`RECORDING_HEADER`].
+    ///
+    /// The sink is written to from a thread of its own. Dropping the transport leaves it to
+    /// write the remaining packets in the background, [`RecordingTransport::finish`] waits for
+    /// them instead.
+    pub fn new<W: Write + Send + 'static>(inner: T, sink: W) -> OpenRGBResult<Self> {
+        Ok(Self {
+            inner,
+            recorder: Mutex::new(Recorder::new(sink)),
+        })
+    }
+
+    /// Records to a new file, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(inner: T, path: P) -> OpenRGBResult<Self> {
+        Self::new(inner, BufWriter::new(File::create(path)?))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Waits for every recorded packet to be written to the sink and returns the connection.
+    pub async fn finish(self) -> OpenRGBResult<T> {
+        let recorder = self.recorder.into_inner().expect("recorder lock poisoned");
+        recorder.finish().await?;
+        Ok(self.inner)
+    }
+
+    fn record(&self, direction: PacketDirection, data: &[u8]) -> std::io::Result<()> {
+        self.recorder
+            .lock()
+            .expect("recorder lock poisoned")
+            .record(direction, data)
+    }
+}
+
+impl<T: Transport> AsyncRead for RecordingTransport<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = result {
+            self.record(PacketDirection::Received, &buf.filled()[filled..])?;
+        }
+
+        result
+    }
+}
+
+impl<T: Transport> AsyncWrite for RecordingTransport<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, data);
+
+        if let Poll::Ready(Ok(written)) = result {
+            self.record(PacketDirection::Sent, &data[..written])?;
+        }
+
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A sink the test can still read once the recorder is done with it.
+    #[derive(Clone, Default)]
+    struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn packet(device: u32, command: Command, payload: &[u8]) -> Vec<u8> {
+        let header = PacketHeader::new(device, command as u32, payload.len() as u32);
+        let mut packet = header.to_bytes().to_vec();
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[tokio::test]
+    async fn reads_back_recorded_packets() {
+        let request = packet(0, Command::RequestControllerCount, &[]);
+        let response = packet(0, Command::RequestControllerCount, &2u32.to_le_bytes());
+        let update = packet(1, Command::UpdateSingleLed, &[3, 0, 0, 0, 255, 0, 0, 0]);
+
+        let sink = SharedSink::default();
+        let (client_end, mut server_end) = tokio::io::duplex(1024);
+        let mut transport = RecordingTransport::new(client_end, sink.clone()).unwrap();
+
+        transport.write_all(&request).await.unwrap();
+        // Packets written in pieces are still recorded whole.
+        transport.write_all(&update[..10]).await.unwrap();
+        transport.write_all(&update[10..]).await.unwrap();
+        server_end.write_all(&response).await.unwrap();
+
+        let mut received = vec![0u8; response.len()];
+        transport.read_exact(&mut received).await.unwrap();
+        transport.finish().await.unwrap();
+
+        let bytes = sink.0.lock().unwrap().clone();
+        assert_eq!(&bytes[..8], RECORDING_HEADER);
+
+        let recording = Recording::read(&bytes[..]).unwrap();
+        let packets: Vec<_> = recording
+            .packets
+            .iter()
+            .map(|packet| (packet.direction, packet.data.clone()))
+            .collect();
+        assert_eq!(
+            packets,
+            vec![
+                (PacketDirection::Sent, request),
+                (PacketDirection::Sent, update),
+                (PacketDirection::Received, response),
+            ]
+        );
+
+        let update = &recording.packets[1];
+        assert_eq!(update.device_id(), 1);
+        assert_eq!(update.command_id(), Command::UpdateSingleLed as u32);
+        assert!(recording
+            .packets
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+    }
+
+    #[test]
+    fn rejects_foreign_files() {
+        assert!(matches!(
+            Recording::read(&b"NOTAREC1"[..]),
+            Err(OpenRGBError::InvalidRecording)
+        ));
+        assert_eq!(
+            Recording::read(&RECORDING_HEADER[..]).unwrap(),
+            Recording::default()
+        );
+    }
+}
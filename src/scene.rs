@@ -0,0 +1,380 @@
+use crate::{
+    Color, DeviceKey, OpenRGBClient, OpenRGBError, OpenRGBMode, OpenRGBResult, COLOR_MODE_PER_LED,
+};
+use std::{convert::TryFrom, fmt::Display};
+
+/// The lighting state of every device, see [`OpenRGBClient::capture_scene`].
+///
+/// With the `serde` feature scenes can be written to and read from files in any format serde
+/// supports.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scene {
+    pub devices: Vec<DeviceScene>,
+}
+
+/// The lighting state of a single device.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceScene {
+    pub key: DeviceKey,
+    /// The active mode with its speed, direction and colors, `None` if the device didn't report
+    /// a valid active mode.
+    pub mode: Option<OpenRGBMode>,
+    pub zone_sizes: Vec<u32>,
+    pub colors: Vec<Color>,
+}
+
+/// Part of a scene that couldn't be applied as captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneMismatch {
+    /// No device matches the key.
+    MissingDevice(DeviceKey),
+    /// More than one device matches the key.
+    AmbiguousDevice(DeviceKey),
+    /// The device has no mode with the name.
+    MissingMode(DeviceKey, String),
+    /// The device has a different number of zones, so none of them were resized.
+    ZoneCount {
+        device: DeviceKey,
+        expected: usize,
+        found: usize,
+    },
+    /// The zone can't be resized to the captured size.
+    ZoneSize {
+        device: DeviceKey,
+        zone_id: usize,
+        expected: u32,
+        found: u32,
+    },
+    /// The device has a different number of leds, only the leds both have were updated.
+    LedCount {
+        device: DeviceKey,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for SceneMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingDevice(device) => write!(f, "no device matches {}", device),
+            Self::AmbiguousDevice(device) => write!(f, "more than one device matches {}", device),
+            Self::MissingMode(device, mode) => write!(f, "{} has no mode {}", device, mode),
+            Self::ZoneCount {
+                device,
+                expected,
+                found,
+            } => write!(f, "{} has {} zones, expected {}", device, found, expected),
+            Self::ZoneSize {
+                device,
+                zone_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "zone {} of {} can't be resized from {} to {} leds",
+                zone_id, device, found, expected
+            ),
+            Self::LedCount {
+                device,
+                expected,
+                found,
+            } => write!(f, "{} has {} leds, expected {}", device, found, expected),
+        }
+    }
+}
+
+/// The outcome of [`OpenRGBClient::apply_scene`].
+#[derive(Debug, Clone, Default)]
+pub struct SceneReport {
+    /// Devices that were found, even if only part of their state could be applied.
+    pub applied: Vec<DeviceKey>,
+    pub mismatches: Vec<SceneMismatch>,
+}
+
+impl SceneReport {
+    /// Returns `true` if the whole scene was applied as captured.
+    pub fn is_complete(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl OpenRGBClient {
+    /// Requests every device and captures its active mode, zone sizes and led colors.
+    ///
+    /// ## Example:
+    /// ```rust
+    /// # use openrgb::*;
+    /// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+    /// let scene = client.capture_scene().await?;
+    ///
+    /// // ...
+    ///
+    /// let report = client.apply_scene(&scene).await?;
+    /// for mismatch in &report.mismatches {
+    ///     println!("{}", mismatch);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn capture_scene(&mut self) -> OpenRGBResult<Scene> {
+        let devices = self
+            .get_all_devices()
+            .await?
+            .iter()
+            .map(|device| DeviceScene {
+                key: DeviceKey::from(device),
+                mode: usize::try_from(device.active_mode)
+                    .ok()
+                    .and_then(|mode_id| device.modes.get(mode_id))
+                    .cloned(),
+                zone_sizes: device.zones.iter().map(|zone| zone.leds_count).collect(),
+                colors: device.colors.clone(),
+            })
+            .collect();
+
+        Ok(Scene { devices })
+    }
+
+    /// Restores a captured scene, matching devices by their [`DeviceKey`] rather than their
+    /// index.
+    ///
+    /// Zones are only resized if their size changed and led colors are only sent if the mode
+    /// shows them. Anything that can't be applied as captured is reported rather than treated
+    /// as an error, so the rest of the scene is still applied.
+    ///
+    /// Scenes hold the colors as the devices showed them, so they are restored without applying
+    /// any color transform a second time.
+    pub async fn apply_scene(&mut self, scene: &Scene) -> OpenRGBResult<SceneReport> {
+        let mut report = SceneReport::default();
+        self.refresh_all().await?;
+
+        for device_scene in &scene.devices {
+            let key = &device_scene.key;
+            let device_id = match self.resolve(key).await {
+                Ok(device_id) => device_id,
+                Err(OpenRGBError::UnknownDevice(_)) => {
+                    report
+                        .mismatches
+                        .push(SceneMismatch::MissingDevice(key.clone()));
+                    continue;
+                }
+                Err(OpenRGBError::AmbiguousDevice(_)) => {
+                    report
+                        .mismatches
+                        .push(SceneMismatch::AmbiguousDevice(key.clone()));
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            self.apply_device_scene(device_id, device_scene, &mut report)
+                .await?;
+            report.applied.push(key.clone());
+        }
+
+        Ok(report)
+    }
+
+    async fn apply_device_scene(
+        &mut self,
+        device_id: usize,
+        scene: &DeviceScene,
+        report: &mut SceneReport,
+    ) -> OpenRGBResult<()> {
+        let key = &scene.key;
+        let zones = self
+            .device(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?
+            .zones
+            .clone();
+
+        if zones.len() != scene.zone_sizes.len() {
+            report.mismatches.push(SceneMismatch::ZoneCount {
+                device: key.clone(),
+                expected: scene.zone_sizes.len(),
+                found: zones.len(),
+            });
+        } else {
+            let mut resized = false;
+
+            for (zone_id, (zone, size)) in zones.iter().zip(&scene.zone_sizes).enumerate() {
+                if zone.leds_count == *size {
+                    continue;
+                }
+
+                if (zone.leds_min..=zone.leds_max).contains(size) {
+                    self.resize_zone(device_id, zone_id, *size).await?;
+                    resized = true;
+                } else {
+                    report.mismatches.push(SceneMismatch::ZoneSize {
+                        device: key.clone(),
+                        zone_id,
+                        expected: *size,
+                        found: zone.leds_count,
+                    });
+                }
+            }
+
+            // Resizing changes the number of leds, which we need to send the colors.
+            if resized {
+                self.refresh_device(device_id).await?;
+            }
+        }
+
+        let mode = match &scene.mode {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+
+        let device = self
+            .device(device_id)
+            .ok_or(OpenRGBError::InvalidId(device_id))?;
+        if !device
+            .modes
+            .iter()
+            .any(|candidate| candidate.name == mode.name)
+        {
+            report
+                .mismatches
+                .push(SceneMismatch::MissingMode(key.clone(), mode.name.clone()));
+            return Ok(());
+        }
+
+        let led_count = device.colors.len();
+        self.update_mode_untransformed(device_id, mode).await?;
+
+        if mode.color_mode == COLOR_MODE_PER_LED {
+            if led_count != scene.colors.len() {
+                report.mismatches.push(SceneMismatch::LedCount {
+                    device: key.clone(),
+                    expected: scene.colors.len(),
+                    found: led_count,
+                });
+            }
+
+            let colors = &scene.colors[..led_count.min(scene.colors.len())];
+            self.update_leds_untransformed(device_id, colors).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::tests::FakeServer, command::Command, types::tests::device, ColorTransform,
+    };
+
+    #[tokio::test]
+    async fn captures_modes_zones_and_colors() {
+        let (mut client, _server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+
+        let scene = client.capture_scene().await.unwrap();
+        assert_eq!(scene.devices.len(), 1);
+
+        let captured = &scene.devices[0];
+        assert_eq!(
+            captured.key,
+            DeviceKey::new("Strip")
+                .with_serial("1")
+                .with_location("HID: /dev/hidraw0")
+        );
+        assert_eq!(captured.mode.as_ref().unwrap().name, "Breathing");
+        assert_eq!(captured.zone_sizes, vec![3, 2]);
+        assert_eq!(captured.colors, device("Strip", "1").colors);
+    }
+
+    #[tokio::test]
+    async fn applies_colors_as_captured() {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+        client.get_device(0).await.unwrap();
+        client
+            .set_color_transform(0, ColorTransform::new(2.0, (1.0, 1.0, 1.0), 0.5))
+            .unwrap();
+
+        let mut scene = client.capture_scene().await.unwrap();
+        scene.devices[0].mode = Some(device("Strip", "1").modes[0].clone());
+        scene.devices[0].colors = vec![Color::WHITE; 5];
+        server.take_requests(&mut client).await;
+
+        let report = client.apply_scene(&scene).await.unwrap();
+        assert!(report.is_complete(), "{:?}", report.mismatches);
+
+        let requests = server.take_requests(&mut client).await;
+        let updates: Vec<_> = requests
+            .iter()
+            .filter(|request| request.command == Command::UpdateLeds as u32)
+            .collect();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].colors(), vec![Color::WHITE; 5]);
+
+        // The transform still applies to later updates, which don't go by the scene's colors.
+        assert!(client.color_transform(0).is_some());
+        assert_eq!(client.colors(0).unwrap(), vec![Color::WHITE; 5].as_slice());
+        client.update_leds(0, &[Color::WHITE; 5]).await.unwrap();
+        let requests = server.take_requests(&mut client).await;
+        assert_ne!(requests[0].colors(), vec![Color::WHITE; 5]);
+    }
+
+    #[tokio::test]
+    async fn applies_mode_colors_as_captured() {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+        client.get_device(0).await.unwrap();
+        client
+            .set_color_transform(0, ColorTransform::new(1.0, (1.0, 1.0, 1.0), 0.5))
+            .unwrap();
+
+        let scene = client.capture_scene().await.unwrap();
+        server.take_requests(&mut client).await;
+        client.apply_scene(&scene).await.unwrap();
+
+        let requests = server.take_requests(&mut client).await;
+        let update = requests
+            .iter()
+            .find(|request| request.command == Command::UpdateMode as u32)
+            .unwrap();
+        assert!(update.payload.ends_with(&[255, 0, 0, 0, 0, 0, 255, 0]));
+        assert!(!requests
+            .iter()
+            .any(|request| request.command == Command::UpdateLeds as u32));
+    }
+
+    #[tokio::test]
+    async fn reports_what_cant_be_applied() {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+
+        let mut scene = client.capture_scene().await.unwrap();
+        scene.devices[0].zone_sizes = vec![4, 3];
+        let mut missing = scene.devices[0].clone();
+        missing.key = DeviceKey::new("Fan");
+        scene.devices.push(missing);
+        server.take_requests(&mut client).await;
+
+        let report = client.apply_scene(&scene).await.unwrap();
+        let key = scene.devices[0].key.clone();
+        assert_eq!(report.applied, vec![key.clone()]);
+        assert_eq!(
+            report.mismatches,
+            vec![
+                SceneMismatch::ZoneSize {
+                    device: key,
+                    zone_id: 1,
+                    expected: 3,
+                    found: 2,
+                },
+                SceneMismatch::MissingDevice(DeviceKey::new("Fan")),
+            ]
+        );
+
+        let requests = server.take_requests(&mut client).await;
+        let resize = requests
+            .iter()
+            .find(|request| request.command == Command::ResizeZone as u32)
+            .unwrap();
+        assert_eq!(resize.payload, [0, 0, 0, 0, 4, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{Color, OpenRGBClient, OpenRGBResult};
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::{sleep_until, Instant};
+
+/// What a submitted frame updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameTarget {
+    Device(usize),
+    Zone(usize, usize),
+}
+
+impl FrameTarget {
+    pub fn device_id(&self) -> usize {
+        match self {
+            Self::Device(device_id) | Self::Zone(device_id, _) => *device_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SchedulerStats {
+    /// Frames passed to the scheduler.
+    pub submitted: u64,
+    /// Frames replaced by a newer frame for the same target before they could be sent.
+    pub dropped: u64,
+    /// Frames sent to OpenRGB.
+    pub sent: u64,
+    /// Flushes per second over the last second.
+    pub achieved_fps: f32,
+}
+
+/// Rate limits updates sent through an [`OpenRGBClient`].
+///
+/// Frames can be submitted at any rate, only the latest frame for each device or zone is kept
+/// and pending frames are sent at most once per frame interval.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # async fn example(client: OpenRGBClient, frames: Vec<Vec<Color>>) -> OpenRGBResult<()> {
+/// let mut scheduler = UpdateScheduler::new(client, 30.0);
+///
+/// for frame in frames {
+///     scheduler.submit_leds(0, frame);
+///     scheduler.flush_if_due().await?;
+/// }
+///
+/// scheduler.flush().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct UpdateScheduler {
+    client: OpenRGBClient,
+    interval: Duration,
+    next_flush: Instant,
+    pending: Vec<(FrameTarget, Vec<Color>)>,
+    flushes: VecDeque<Instant>,
+    stats: SchedulerStats,
+}
+
+impl UpdateScheduler {
+    pub fn new(client: OpenRGBClient, fps: f32) -> Self {
+        Self {
+            client,
+            interval: Self::fps_to_interval(fps),
+            next_flush: Instant::now(),
+            pending: Vec::new(),
+            flushes: VecDeque::new(),
+            stats: SchedulerStats::default(),
+        }
+    }
+
+    pub fn client(&mut self) -> &mut OpenRGBClient {
+        &mut self.client
+    }
+
+    pub fn into_inner(self) -> OpenRGBClient {
+        self.client
+    }
+
+    pub fn set_fps(&mut self, fps: f32) {
+        self.interval = Self::fps_to_interval(fps);
+    }
+
+    pub fn stats(&self) -> SchedulerStats {
+        let now = Instant::now();
+        let mut stats = self.stats.clone();
+        stats.achieved_fps = self
+            .flushes
+            .iter()
+            .filter(|flush| now.duration_since(**flush) < Duration::from_secs(1))
+            .count() as f32;
+        stats
+    }
+
+    /// Queues colors for all of the device's leds, replacing any pending frame for the device
+    /// or its zones.
+    pub fn submit_leds(&mut self, device_id: usize, colors: Vec<Color>) {
+        let before = self.pending.len();
+        self.pending.retain(|(target, _)| match target {
+            FrameTarget::Zone(zone_device_id, _) => *zone_device_id != device_id,
+            FrameTarget::Device(_) => true,
+        });
+        self.stats.dropped += (before - self.pending.len()) as u64;
+
+        self.submit(FrameTarget::Device(device_id), colors);
+    }
+
+    /// Queues colors for the leds of one zone, replacing any pending frame for the zone.
+    pub fn submit_zone_leds(&mut self, device_id: usize, zone_id: usize, colors: Vec<Color>) {
+        self.submit(FrameTarget::Zone(device_id, zone_id), colors);
+    }
+
+    fn submit(&mut self, target: FrameTarget, colors: Vec<Color>) {
+        self.stats.submitted += 1;
+
+        match self
+            .pending
+            .iter_mut()
+            .find(|(pending, _)| *pending == target)
+        {
+            Some((_, pending_colors)) => {
+                *pending_colors = colors;
+                self.stats.dropped += 1;
+            }
+            None => self.pending.push((target, colors)),
+        }
+    }
+
+    /// Returns `true` if a frame interval has passed since the last flush.
+    pub fn is_due(&self) -> bool {
+        Instant::now() >= self.next_flush
+    }
+
+    /// Sends the pending frames if a frame interval has passed since the last flush, returns
+    /// whether anything was sent.
+    pub async fn flush_if_due(&mut self) -> OpenRGBResult<bool> {
+        if !self.is_due() || self.pending.is_empty() {
+            return Ok(false);
+        }
+
+        self.flush().await.map(|_| true)
+    }
+
+    /// Waits for the next frame interval and then sends the pending frames.
+    pub async fn tick(&mut self) -> OpenRGBResult<()> {
+        sleep_until(self.next_flush).await;
+        self.flush().await
+    }
+
+    /// Sends all pending frames immediately.
+    ///
+    /// If a frame can't be sent it stays pending along with the frames after it, unless a newer
+    /// frame for the same target is submitted before the next flush.
+    pub async fn flush(&mut self) -> OpenRGBResult<()> {
+        let now = Instant::now();
+        self.next_flush = if self.next_flush + self.interval > now {
+            self.next_flush + self.interval
+        } else {
+            // We fell behind, don't try to catch up on the missed frames.
+            now + self.interval
+        };
+
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        while let Some((target, colors)) = self.pending.first() {
+            match *target {
+                FrameTarget::Device(device_id) => {
+                    self.client.update_leds(device_id, colors).await?
+                }
+                FrameTarget::Zone(device_id, zone_id) => {
+                    self.client
+                        .update_zone_leds(device_id, zone_id, colors)
+                        .await?
+                }
+            }
+
+            self.pending.remove(0);
+            self.stats.sent += 1;
+        }
+
+        self.flushes.push_back(now);
+        while let Some(oldest) = self.flushes.front() {
+            if now.duration_since(*oldest) < Duration::from_secs(1) {
+                break;
+            }
+
+            self.flushes.pop_front();
+        }
+
+        Ok(())
+    }
+
+    fn fps_to_interval(fps: f32) -> Duration {
+        Duration::from_secs_f32(1.0 / fps.max(f32::EPSILON))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::tests::FakeServer, command::Command, types::tests::device};
+    use tokio::time;
+
+    async fn scheduler(fps: f32) -> (UpdateScheduler, FakeServer) {
+        let (mut client, server) = FakeServer::connect(vec![device("Strip", "1")]).await;
+        client.get_device(0).await.unwrap();
+        server.take_requests(&mut client).await;
+
+        (UpdateScheduler::new(client, fps), server)
+    }
+
+    #[tokio::test]
+    async fn coalesces_frames_for_the_same_leds() {
+        let (mut scheduler, server) = scheduler(30.0).await;
+
+        scheduler.submit_zone_leds(0, 1, vec![Color::RED; 2]);
+        scheduler.submit_leds(0, vec![Color::RED; 5]);
+        scheduler.submit_zone_leds(0, 0, vec![Color::GREEN; 3]);
+        scheduler.submit_leds(0, vec![Color::BLUE; 5]);
+        scheduler.submit_zone_leds(0, 1, vec![Color::WHITE; 2]);
+        scheduler.flush().await.unwrap();
+
+        let requests = server.take_requests(scheduler.client()).await;
+        let sent: Vec<(u32, Vec<Color>)> = requests
+            .iter()
+            .map(|request| (request.command, request.colors()))
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                (Command::UpdateLeds as u32, vec![Color::BLUE; 5]),
+                (Command::UpdateZoneLeds as u32, vec![Color::WHITE; 2]),
+            ]
+        );
+
+        let stats = scheduler.stats();
+        assert_eq!((stats.submitted, stats.dropped, stats.sent), (5, 3, 2));
+    }
+
+    #[tokio::test]
+    async fn flushes_once_per_interval() {
+        time::pause();
+        let (mut scheduler, _server) = scheduler(8.0).await;
+
+        scheduler.submit_leds(0, vec![Color::RED; 5]);
+        assert!(scheduler.flush_if_due().await.unwrap());
+
+        scheduler.submit_leds(0, vec![Color::GREEN; 5]);
+        assert!(!scheduler.flush_if_due().await.unwrap());
+        time::advance(Duration::from_millis(100)).await;
+        assert!(!scheduler.flush_if_due().await.unwrap());
+        time::advance(Duration::from_millis(25)).await;
+        assert!(scheduler.flush_if_due().await.unwrap());
+
+        // Ticks wait for the rest of the interval, give or take the timer's resolution.
+        let start = Instant::now();
+        scheduler.tick().await.unwrap();
+        let waited = start.elapsed();
+        assert!(
+            (Duration::from_millis(125)..Duration::from_millis(130)).contains(&waited),
+            "{:?}",
+            waited
+        );
+
+        // Nothing pending, nothing sent.
+        time::advance(Duration::from_millis(125)).await;
+        assert!(!scheduler.flush_if_due().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn counts_flushes_over_the_last_second() {
+        time::pause();
+        let (mut scheduler, _server) = scheduler(10.0).await;
+
+        for _ in 0..3 {
+            scheduler.submit_leds(0, vec![Color::RED; 5]);
+            scheduler.tick().await.unwrap();
+        }
+        assert_eq!(scheduler.stats().achieved_fps, 3.0);
+
+        time::advance(Duration::from_secs(2)).await;
+        assert_eq!(scheduler.stats().achieved_fps, 0.0);
+    }
+
+    #[tokio::test]
+    async fn keeps_frames_that_werent_sent() {
+        let (mut scheduler, server) = scheduler(30.0).await;
+
+        scheduler.submit_zone_leds(0, 0, vec![Color::RED; 3]);
+        scheduler.submit_zone_leds(0, 1, vec![Color::GREEN; 2]);
+        server.hang_up_after(1);
+        assert!(scheduler.flush().await.is_err());
+        assert_eq!(scheduler.stats().sent, 1);
+        assert_eq!(
+            scheduler.pending,
+            vec![(FrameTarget::Zone(0, 1), vec![Color::GREEN; 2])]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    command::Command,
+    network::{
+        connection::{decode_payload, PacketHeader, HEADER_LENGTH},
+        packet::{OpenRGBPacket, OpenRGBPackets},
+    },
+    OpenRGBResult,
+};
+use async_trait::async_trait;
+use std::{convert::TryFrom, future::Future, net::SocketAddr};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+};
+
+/// A packet with its payload left as it was sent, so it can be inspected or passed on without
+/// understanding every command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPacket {
+    pub device: u32,
+    pub command: u32,
+    pub payload: Vec<u8>,
+}
+
+impl RawPacket {
+    pub fn new(device: u32, command: u32, payload: Vec<u8>) -> Self {
+        Self {
+            device,
+            command,
+            payload,
+        }
+    }
+
+    pub(crate) async fn from_packet<P: OpenRGBPacket>(
+        device: u32,
+        packet: &P,
+    ) -> OpenRGBResult<Self> {
+        let mut payload = Vec::new();
+        packet.serialize(&mut payload).await?;
+        Ok(Self::new(device, packet.command() as u32, payload))
+    }
+
+    /// Reads the next packet, returning `None` if the connection was closed between packets.
+    ///
+    /// Packets announcing a payload larger than 16 MiB are refused with
+    /// [`crate::OpenRGBError::PacketTooLarge`] before anything is allocated for them.
+    pub async fn read<R: AsyncRead + Unpin + Send>(reader: &mut R) -> OpenRGBResult<Option<Self>> {
+        let mut header = [0u8; HEADER_LENGTH];
+        let mut filled = 0;
+        while filled < header.len() {
+            match reader.read(&mut header[filled..]).await? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                read => filled += read,
+            }
+        }
+
+        let header = PacketHeader::parse(&header)?;
+        let mut payload = vec![0u8; header.length as usize];
+        reader.read_exact(&mut payload).await?;
+
+        Ok(Some(Self::new(header.device, header.command, payload)))
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> OpenRGBResult<()> {
+        let header = PacketHeader::new(self.device, self.command, self.payload.len() as u32);
+
+        let mut buffer = Vec::with_capacity(HEADER_LENGTH + self.payload.len());
+        buffer.extend_from_slice(&header.to_bytes());
+        buffer.extend_from_slice(&self.payload);
+
+        writer.write_all(&buffer).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Parses the packet the way the client does.
+    pub async fn decode(&self) -> OpenRGBResult<OpenRGBPackets> {
+        decode_payload(Command::try_from(self.command)?, &self.payload).await
+    }
+}
+
+/// Sends packets to a connected client outside of answering its requests, such as forwarded
+/// `DeviceListUpdated` notifications.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sender: UnboundedSender<RawPacket>,
+}
+
+impl Notifier {
+    /// Queues a packet, returning `false` if the client has disconnected.
+    pub fn send(&self, packet: RawPacket) -> bool {
+        self.sender.send(packet).is_ok()
+    }
+}
+
+/// Answers the requests of a single client.
+#[async_trait]
+pub trait ServerHandler: Send + 'static {
+    /// Handles a request, returning the packets to send back in order. Commands that don't
+    /// expect an answer should return nothing.
+    async fn handle(&mut self, request: RawPacket) -> OpenRGBResult<Vec<RawPacket>>;
+}
+
+/// A server speaking the OpenRGB SDK protocol, creating a [`ServerHandler`] for every client
+/// that connects.
+///
+/// Clients are served concurrently. A client whose handler fails to be created or returns an
+/// error is disconnected without affecting the others.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # use async_trait::async_trait;
+/// struct Empty;
+///
+/// #[async_trait]
+/// impl ServerHandler for Empty {
+///     async fn handle(&mut self, request: RawPacket) -> OpenRGBResult<Vec<RawPacket>> {
+///         Ok(match request.command {
+///             // RequestControllerCount
+///             0 => vec![RawPacket::new(0, 0, 0u32.to_le_bytes().to_vec())],
+///             _ => Vec::new(),
+///         })
+///     }
+/// }
+///
+/// # async fn example() -> OpenRGBResult<()> {
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:6742").await?;
+/// OpenRGBServer::new(|_, _| async { Ok(Empty) }).serve(listener).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenRGBServer<F> {
+    new_handler: F,
+}
+
+impl<F, Fut, H> OpenRGBServer<F>
+where
+    F: FnMut(SocketAddr, Notifier) -> Fut,
+    Fut: Future<Output = OpenRGBResult<H>> + Send + 'static,
+    H: ServerHandler,
+{
+    /// Creates a server calling `new_handler` with the address of every client that connects
+    /// and a [`Notifier`] for sending it packets at any time.
+    pub fn new(new_handler: F) -> Self {
+        Self { new_handler }
+    }
+
+    pub async fn serve(mut self, listener: TcpListener) -> OpenRGBResult<()> {
+        loop {
+            let (stream, address) = listener.accept().await?;
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let handler = (self.new_handler)(
+                address,
+                Notifier {
+                    sender: sender.clone(),
+                },
+            );
+
+            tokio::spawn(async move {
+                if let Ok(handler) = handler.await {
+                    let _ = serve_client(stream, handler, sender, receiver).await;
+                }
+            });
+        }
+    }
+}
+
+async fn serve_client<H: ServerHandler>(
+    stream: TcpStream,
+    mut handler: H,
+    sender: UnboundedSender<RawPacket>,
+    mut receiver: UnboundedReceiver<RawPacket>,
+) -> OpenRGBResult<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let (finished, mut finish) = oneshot::channel::<()>();
+
+    // Answers and notifications share a queue so packets are never interleaved. Once the client
+    // stops sending, whatever is queued is still written, but notifiers kept elsewhere don't keep
+    // the connection alive.
+    let writes = tokio::spawn(async move {
+        loop {
+            let packet = tokio::select! {
+                packet = receiver.recv() => packet,
+                _ = &mut finish => {
+                    while let Ok(packet) = receiver.try_recv() {
+                        if packet.write(&mut writer).await.is_err() {
+                            break;
+                        }
+                    }
+                    break;
+                }
+            };
+
+            match packet {
+                Some(packet) if packet.write(&mut writer).await.is_ok() => {}
+                _ => break,
+            }
+        }
+    });
+
+    let result = async {
+        while let Some(request) = RawPacket::read(&mut reader).await? {
+            for response in handler.handle(request).await? {
+                if sender.send(response).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    drop(handler);
+    drop(sender);
+    let _ = finished.send(());
+    let _ = writes.await;
+    result
+}
@@ -0,0 +1,255 @@
+use crate::{Color, MatrixFrame, OpenRGBClient, OpenRGBResult};
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+const GLYPH_SPACING: u32 = 1;
+
+/// Gets the 3x5 glyph of the character as rows from top to bottom, the leftmost pixel of a row
+/// being its highest bit. Lowercase letters use their uppercase glyph and unknown characters
+/// are drawn as `?`.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '*' => [0b101, 0b010, 0b101, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Gets the width in pixels of the text when drawn with [`draw_text`].
+pub fn text_width(text: &str) -> u32 {
+    match text.chars().count() as u32 {
+        0 => 0,
+        count => count * (GLYPH_WIDTH + GLYPH_SPACING) - GLYPH_SPACING,
+    }
+}
+
+/// Draws the text with its top left corner at `(x, y)` using the built-in 3x5 font, pixels
+/// outside of the frame are clipped.
+pub fn draw_text(frame: &mut MatrixFrame, text: &str, x: i32, y: i32, color: Color) {
+    for (index, c) in text.chars().enumerate() {
+        let glyph_x = x + (index as u32 * (GLYPH_WIDTH + GLYPH_SPACING)) as i32;
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                let pixel_x = glyph_x + column as i32;
+                let pixel_y = y + row as i32;
+
+                if pixel_x >= 0 && pixel_y >= 0 {
+                    frame.set(pixel_x as u32, pixel_y as u32, color);
+                }
+            }
+        }
+    }
+}
+
+/// Text scrolling from right to left across a matrix, entering at the right edge and starting
+/// over once it has left the left edge.
+///
+/// ## Example:
+/// ```rust
+/// # use openrgb::*;
+/// # use std::time::Duration;
+/// # async fn example(mut client: OpenRGBClient) -> OpenRGBResult<()> {
+/// client.get_device(0).await?;
+/// let mut marquee = Marquee::new("Build passed", Color::GREEN, Color::BLACK);
+/// client
+///     .play_marquee(0, 0, &mut marquee, Duration::from_millis(100), Some(1))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Marquee {
+    pub text: String,
+    pub foreground: Color,
+    pub background: Color,
+    offset: u32,
+}
+
+impl Marquee {
+    pub fn new<S: Into<String>>(text: S, foreground: Color, background: Color) -> Self {
+        Self {
+            text: text.into(),
+            foreground,
+            background,
+            offset: 0,
+        }
+    }
+
+    /// Number of steps it takes the text to scroll fully across a frame of the width.
+    pub fn cycle_length(&self, width: u32) -> u32 {
+        width + text_width(&self.text)
+    }
+
+    /// Scrolls the text one pixel to the left.
+    pub fn step(&mut self) {
+        self.offset = self.offset.wrapping_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Draws the text at its current position over the background, vertically centered.
+    pub fn render(&self, frame: &mut MatrixFrame) {
+        frame.fill(self.background);
+
+        let position = self.offset % self.cycle_length(frame.width()).max(1);
+        let x = frame.width() as i32 - position as i32;
+        let y = (frame.height() as i32 - GLYPH_HEIGHT as i32) / 2;
+
+        draw_text(frame, &self.text, x, y.max(0), self.foreground);
+    }
+}
+
+impl OpenRGBClient {
+    /// Scrolls the marquee across a matrix zone, moving it one pixel every `step`.
+    ///
+    /// The text scrolls across the zone `cycles` times, or forever if `None`.
+    pub async fn play_marquee(
+        &mut self,
+        device_id: usize,
+        zone_id: usize,
+        marquee: &mut Marquee,
+        step: Duration,
+        cycles: Option<usize>,
+    ) -> OpenRGBResult<()> {
+        let mut frame = MatrixFrame::for_map(self.matrix_map(device_id, zone_id)?);
+        let cycle_length = marquee.cycle_length(frame.width()) as usize;
+
+        let mut steps = 0;
+        while cycles.map(|cycles| cycles * cycle_length) != Some(steps) {
+            marquee.render(&mut frame);
+            self.update_matrix(device_id, zone_id, &frame).await?;
+
+            marquee.step();
+            steps += 1;
+            sleep(step).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws the frame as rows of `#` for red and `.` for anything else.
+    fn rows(frame: &MatrixFrame) -> Vec<String> {
+        (0..frame.height())
+            .map(|y| {
+                (0..frame.width())
+                    .map(|x| match frame.get(x, y) {
+                        Some(Color::RED) => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_glyphs() {
+        let mut frame = MatrixFrame::new(3, 5);
+        draw_text(&mut frame, "a", 0, 0, Color::RED);
+        assert_eq!(rows(&frame), vec![".#.", "#.#", "###", "#.#", "#.#"]);
+        assert_eq!(frame.get(0, 0), None);
+    }
+
+    #[test]
+    fn clips_text_outside_the_frame() {
+        let mut frame = MatrixFrame::new(3, 5);
+        draw_text(&mut frame, "HI", -2, 1, Color::RED);
+        assert_eq!(rows(&frame), vec!["...", "#.#", "#..", "#..", "#.."]);
+    }
+
+    #[test]
+    fn measures_text() {
+        assert_eq!(text_width(""), 0);
+        assert_eq!(text_width("A"), 3);
+        assert_eq!(text_width("AB"), 7);
+    }
+
+    #[test]
+    fn marquee_scrolls_in_and_wraps_around() {
+        let mut marquee = Marquee::new("I", Color::RED, Color::BLACK);
+        let mut frame = MatrixFrame::new(4, 5);
+        assert_eq!(marquee.cycle_length(4), 7);
+
+        let mut shown = Vec::new();
+        for _ in 0..8 {
+            marquee.render(&mut frame);
+            shown.push(rows(&frame)[0].clone());
+            marquee.step();
+        }
+
+        assert_eq!(
+            shown,
+            vec!["....", "...#", "..##", ".###", "###.", "##..", "#...", "...."]
+        );
+        assert_eq!(frame.get(3, 4), Some(Color::BLACK));
+    }
+}
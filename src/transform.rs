@@ -0,0 +1,151 @@
+use crate::{Color, OpenRGBDevice};
+
+/// A perceptual correction applied to colors right before they are sent to a device.
+///
+/// Channels are first scaled by the white balance and the brightness cap, then raised to the
+/// power of `gamma`. The default transform leaves colors untouched.
+///
+/// With the `serde` feature the transforms of a client can be saved and loaded keyed by
+/// [`ColorTransform::device_key`], see [`crate::OpenRGBClient::save_color_transforms`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorTransform {
+    /// Exponent of the gamma curve, values above `1.0` darken the low end of the range.
+    pub gamma: f32,
+    /// Per-channel scale used to correct tinted whites, each in `0.0..=1.0`.
+    pub white_balance: (f32, f32, f32),
+    /// Global brightness cap in `0.0..=1.0`.
+    pub brightness: f32,
+}
+
+impl ColorTransform {
+    pub fn new(gamma: f32, white_balance: (f32, f32, f32), brightness: f32) -> Self {
+        Self {
+            gamma,
+            white_balance,
+            brightness,
+        }
+    }
+
+    /// Returns `true` if applying this transform would not change any color.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        if self.is_identity() {
+            return color;
+        }
+
+        let (r_scale, g_scale, b_scale) = self.white_balance;
+        let brightness = self.brightness.clamp(0.0, 1.0);
+        let gamma = self.gamma.max(f32::EPSILON);
+
+        let correct = |channel: u8, scale: f32| {
+            let linear = channel as f32 / 255.0 * scale.clamp(0.0, 1.0) * brightness;
+            (linear.powf(gamma) * 255.0).round() as u8
+        };
+
+        Color::new(
+            correct(color.r, r_scale),
+            correct(color.g, g_scale),
+            correct(color.b, b_scale),
+        )
+    }
+
+    pub fn apply_all(&self, colors: &[Color]) -> Vec<Color> {
+        colors.iter().map(|color| self.apply(*color)).collect()
+    }
+
+    /// The key transforms are stored under for a device, its serial or name and location if the
+    /// device doesn't report one.
+    pub fn device_key(device: &OpenRGBDevice) -> String {
+        if device.serial.is_empty() {
+            format!("{}@{}", device.name, device.location)
+        } else {
+            device.serial.clone()
+        }
+    }
+}
+
+impl Default for ColorTransform {
+    fn default() -> Self {
+        Self::new(1.0, (1.0, 1.0, 1.0), 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::tests::device;
+
+    #[test]
+    fn applies_each_correction() {
+        let white = Color::WHITE;
+        let cases = [
+            (
+                ColorTransform::default(),
+                Color::new(12, 128, 250),
+                Color::new(12, 128, 250),
+            ),
+            (ColorTransform::new(2.0, (1.0, 1.0, 1.0), 1.0), white, white),
+            (
+                ColorTransform::new(2.0, (1.0, 1.0, 1.0), 1.0),
+                Color::new(0, 128, 255),
+                Color::new(0, 64, 255),
+            ),
+            (
+                ColorTransform::new(0.5, (1.0, 1.0, 1.0), 1.0),
+                Color::new(64, 0, 0),
+                Color::new(128, 0, 0),
+            ),
+            (
+                ColorTransform::new(1.0, (1.0, 0.5, 0.0), 1.0),
+                white,
+                Color::new(255, 128, 0),
+            ),
+            (
+                ColorTransform::new(1.0, (1.0, 1.0, 1.0), 0.5),
+                Color::new(200, 100, 0),
+                Color::new(100, 50, 0),
+            ),
+            // Combined, the channels are scaled before the gamma curve.
+            (
+                ColorTransform::new(2.0, (1.0, 0.5, 1.0), 0.5),
+                white,
+                Color::new(64, 16, 64),
+            ),
+            // Out of range scales are clamped.
+            (
+                ColorTransform::new(1.0, (1.5, -1.0, 1.0), 2.0),
+                white,
+                Color::new(255, 0, 255),
+            ),
+        ];
+
+        for (transform, color, expected) in &cases {
+            assert_eq!(transform.apply(*color), *expected, "{:?}", transform);
+        }
+    }
+
+    #[test]
+    fn applies_to_every_color() {
+        let transform = ColorTransform::new(1.0, (1.0, 1.0, 1.0), 0.5);
+
+        assert!(ColorTransform::default().is_identity());
+        assert!(!transform.is_identity());
+        assert_eq!(
+            transform.apply_all(&[Color::WHITE, Color::BLACK]),
+            vec![Color::new(128, 128, 128), Color::BLACK]
+        );
+    }
+
+    #[test]
+    fn keys_devices_by_serial_or_location() {
+        assert_eq!(ColorTransform::device_key(&device("Strip", "1234")), "1234");
+        assert_eq!(
+            ColorTransform::device_key(&device("Strip", "")),
+            "Strip@HID: /dev/hidraw0"
+        );
+    }
+}
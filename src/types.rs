@@ -1,14 +1,16 @@
 use crate::{
     io::{AsyncOpenRGBReadExt, AsyncOpenRGBWriteExt, OpenRGBSendable},
-    OpenRGBResult,
+    Color, OpenRGBResult,
 };
 use async_trait::async_trait;
 use tokio::io::AsyncWriteExt;
 
-pub type OpenRGBColor = (u8, u8, u8);
+/// Kept for compatibility, prefer using [`Color`] directly.
+pub type OpenRGBColor = Color;
 pub type OpenRGBZoneType = u32;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenRGBMode {
     pub name: String,
     pub value: i32,
@@ -20,16 +22,25 @@ pub struct OpenRGBMode {
     pub speed: u32,
     pub direction: u32,
     pub color_mode: u32,
-    pub colors: Vec<OpenRGBColor>,
+    pub colors: Vec<Color>,
 }
 
+/// OpenRGB's `MODE_COLORS_PER_LED`, the [`OpenRGBMode::color_mode`] of modes showing the colors
+/// set with `update_leds`.
+pub const COLOR_MODE_PER_LED: u32 = 1;
+/// OpenRGB's `MODE_COLORS_MODE_SPECIFIC`, the [`OpenRGBMode::color_mode`] of modes showing their
+/// own [`OpenRGBMode::colors`].
+pub const COLOR_MODE_SPECIFIC: u32 = 2;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenRGBLed {
     pub name: String,
     pub value: u32,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenRGBMatrixMap {
     pub height: u32,
     pub width: u32,
@@ -37,6 +48,7 @@ pub struct OpenRGBMatrixMap {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenRGBZone {
     pub name: String,
     // TODO: Refactor this to an enum.
@@ -48,6 +60,7 @@ pub struct OpenRGBZone {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenRGBDevice {
     pub name: String,
     pub description: String,
@@ -57,11 +70,23 @@ pub struct OpenRGBDevice {
     pub leds: Vec<OpenRGBLed>,
     pub zones: Vec<OpenRGBZone>,
     pub modes: Vec<OpenRGBMode>,
-    pub colors: Vec<OpenRGBColor>,
+    pub colors: Vec<Color>,
     pub device_type: u32,
     pub active_mode: i32,
 }
 
+impl OpenRGBDevice {
+    /// Gets the index of the zone's first led within `leds` and `colors`.
+    pub fn zone_led_offset(&self, zone_id: usize) -> Option<usize> {
+        if zone_id >= self.zones.len() {
+            return None;
+        }
+
+        let previous = &self.zones[..zone_id];
+        Some(previous.iter().map(|zone| zone.leds_count as usize).sum())
+    }
+}
+
 #[async_trait]
 impl OpenRGBSendable for OpenRGBDevice {
     type Output = Self;
@@ -80,6 +105,7 @@ impl OpenRGBSendable for OpenRGBDevice {
         buffer.write_string(&self.location).await?;
 
         buffer.write_u16_le(self.modes.len() as u16).await?;
+        buffer.write_i32_le(self.active_mode).await?;
         for mode in &self.modes {
             mode.serialize(&mut buffer).await?;
         }
@@ -99,7 +125,8 @@ impl OpenRGBSendable for OpenRGBDevice {
             color.serialize(&mut buffer).await?
         }
 
-        writer.write_u32_le(buffer.len() as u32).await?;
+        // The size includes its own four bytes.
+        writer.write_u32_le(buffer.len() as u32 + 4).await?;
         writer.write_all(&buffer).await?;
 
         Ok(())
@@ -141,10 +168,10 @@ impl OpenRGBSendable for OpenRGBDevice {
         }
 
         let color_count = reader.read_u16_le().await? as usize;
-        let mut colors: Vec<OpenRGBColor> = Vec::with_capacity(color_count);
+        let mut colors: Vec<Color> = Vec::with_capacity(color_count);
 
         for _ in 0..color_count {
-            let color = OpenRGBColor::deserialize(reader).await?;
+            let color = Color::deserialize(reader).await?;
             colors.push(color);
         }
 
@@ -207,7 +234,7 @@ impl OpenRGBSendable for OpenRGBMode {
         let mut colors = Vec::with_capacity(color_count);
 
         for _ in 0..color_count {
-            let color = OpenRGBColor::deserialize(reader).await?;
+            let color = Color::deserialize(reader).await?;
             colors.push(color);
         }
 
@@ -333,17 +360,15 @@ impl OpenRGBSendable for OpenRGBLed {
 }
 
 #[async_trait]
-impl OpenRGBSendable for OpenRGBColor {
+impl OpenRGBSendable for Color {
     type Output = Self;
     async fn serialize<W: AsyncOpenRGBWriteExt + Send + Unpin>(
         &self,
         writer: &mut W,
     ) -> OpenRGBResult<()> {
-        let (r, g, b) = *self;
-
-        writer.write_u8(r).await?;
-        writer.write_u8(g).await?;
-        writer.write_u8(b).await?;
+        writer.write_u8(self.r).await?;
+        writer.write_u8(self.g).await?;
+        writer.write_u8(self.b).await?;
         writer.write_u8(0).await?;
 
         Ok(())
@@ -354,6 +379,116 @@ impl OpenRGBSendable for OpenRGBColor {
         let b = reader.read_u8().await?;
         let _ = reader.read_u8().await?;
 
-        Ok((r, g, b))
+        Ok(Color::new(r, g, b))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    pub(crate) fn device(name: &str, serial: &str) -> OpenRGBDevice {
+        OpenRGBDevice {
+            name: name.to_string(),
+            description: "A device".to_string(),
+            version: "1.0".to_string(),
+            serial: serial.to_string(),
+            location: "HID: /dev/hidraw0".to_string(),
+            leds: (0..5)
+                .map(|value| OpenRGBLed {
+                    name: format!("Led {}", value),
+                    value,
+                })
+                .collect(),
+            zones: vec![
+                OpenRGBZone {
+                    name: "Strip".to_string(),
+                    zone_type: 1,
+                    leds_count: 3,
+                    leds_min: 0,
+                    leds_max: 10,
+                    matrix_map: None,
+                },
+                OpenRGBZone {
+                    name: "Matrix".to_string(),
+                    zone_type: 2,
+                    leds_count: 2,
+                    leds_min: 2,
+                    leds_max: 2,
+                    matrix_map: Some(OpenRGBMatrixMap {
+                        height: 1,
+                        width: 3,
+                        map: vec![0, u32::MAX, 1],
+                    }),
+                },
+            ],
+            modes: vec![
+                OpenRGBMode {
+                    name: "Direct".to_string(),
+                    value: 0,
+                    flags: 0,
+                    speed_min: 0,
+                    speed_max: 0,
+                    colors_min: 0,
+                    colors_max: 0,
+                    speed: 0,
+                    direction: 0,
+                    color_mode: COLOR_MODE_PER_LED,
+                    colors: vec![],
+                },
+                OpenRGBMode {
+                    name: "Breathing".to_string(),
+                    value: -1,
+                    flags: 3,
+                    speed_min: 1,
+                    speed_max: 5,
+                    colors_min: 1,
+                    colors_max: 2,
+                    speed: 3,
+                    direction: 1,
+                    color_mode: COLOR_MODE_SPECIFIC,
+                    colors: vec![Color::RED, Color::BLUE],
+                },
+            ],
+            colors: vec![
+                Color::RED,
+                Color::GREEN,
+                Color::BLUE,
+                Color::WHITE,
+                Color::BLACK,
+            ],
+            device_type: 4,
+            active_mode: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn device_round_trip() {
+        let device = device("Strip", "1234");
+
+        let mut buffer = Vec::new();
+        device.serialize(&mut buffer).await.unwrap();
+
+        let size = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        assert_eq!(size as usize, buffer.len());
+
+        let mut reader = Cursor::new(&buffer);
+        let read = OpenRGBDevice::deserialize(&mut reader).await.unwrap();
+        assert_eq!(reader.position() as usize, buffer.len());
+        assert_eq!(format!("{:?}", read), format!("{:?}", device));
+    }
+
+    #[tokio::test]
+    async fn truncated_device() {
+        let mut buffer = Vec::new();
+        device("Strip", "1234")
+            .serialize(&mut buffer)
+            .await
+            .unwrap();
+        buffer.truncate(buffer.len() - 1);
+
+        let result = OpenRGBDevice::deserialize(&mut buffer.as_slice()).await;
+        assert!(matches!(result, Err(crate::OpenRGBError::IO(_))));
     }
 }